@@ -0,0 +1,40 @@
+use std::{env, path::PathBuf, process::Command};
+
+// keep in sync with `src/shader.rs`.
+const SHADERS: &[(&str, &str)] = &[
+    ("vs_egui", "vs_5_0"),
+    ("ps_egui", "ps_5_0"),
+    ("ps_egui_straight", "ps_5_0"),
+    ("ps_egui_straight_premultiplied", "ps_5_0"),
+    ("ps_egui_mask", "ps_5_0"),
+    ("ps_egui_nv12", "ps_5_0"),
+    ("ps_egui_id", "ps_5_0"),
+    ("vs_blit", "vs_5_0"),
+    ("ps_blit_downscale", "ps_5_0"),
+];
+
+fn main() {
+    println!("cargo::rerun-if-changed=shaders/egui.hlsl");
+    println!("cargo::rerun-if-env-changed=FXC");
+
+    if env::var_os("CARGO_FEATURE_PRECOMPILED").is_none() {
+        return;
+    }
+
+    // `fxc` ships with the Windows SDK. Set `FXC` to its full path if it is
+    // not in `PATH`.
+    let fxc = env::var_os("FXC").unwrap_or_else(|| "fxc".into());
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    for (entry, target) in SHADERS {
+        let output = out_dir.join(format!("{entry}.cso"));
+        let status = Command::new(&fxc)
+            .args(["shaders/egui.hlsl", "/nologo", "/O3"])
+            .args(["/T", target, "/E", entry, "/Fo"])
+            .arg(&output)
+            .status()
+            .unwrap_or_else(|err| {
+                panic!("failed to run fxc ({err}), set `FXC` to its path")
+            });
+        assert!(status.success(), "failed to compile shader {entry}");
+    }
+}
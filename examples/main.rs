@@ -79,6 +79,7 @@ struct DemoApp {
     egui_ctx: egui::Context,
     egui_renderer: egui_directx11::Renderer,
     egui_winit: egui_winit::State,
+    viewport_info: egui::ViewportInfo,
     state: DemoState,
 }
 
@@ -123,7 +124,7 @@ impl App for DemoApp {
             &window,
             None,
             None,
-            None,
+            Some(egui_renderer.max_texture_dimension()),
         );
 
         Self {
@@ -134,6 +135,7 @@ impl App for DemoApp {
             egui_ctx,
             egui_renderer,
             egui_winit,
+            viewport_info: egui::ViewportInfo::default(),
             state: DemoState::default(),
         }
     }
@@ -157,8 +159,11 @@ impl DemoApp {
             let egui_output = self.egui_ctx.run(egui_input, |ctx| {
                 self.state.ui(&ctx);
             });
-            let (renderer_output, platform_output, _) =
-                egui_directx11::split_output(egui_output);
+            let egui_directx11::SplitOutput {
+                renderer_output,
+                platform_output,
+                viewport_output,
+            } = egui_directx11::split_output(egui_output);
             self.egui_winit
                 .handle_platform_output(window, platform_output);
             unsafe {
@@ -174,11 +179,32 @@ impl DemoApp {
                 renderer_output,
             );
             let _ = unsafe { self.swap_chain.Present(1, DXGI_PRESENT(0)) };
+            self.handle_viewport_output(window, viewport_output);
         } else {
             unreachable!()
         }
     }
 
+    fn handle_viewport_output(
+        &mut self,
+        window: &Window,
+        mut viewport_output: egui_directx11::ViewportOutputs,
+    ) {
+        // This example has only a single window, so only commands sent to the
+        // root viewport are handled. A multi-viewport application would also
+        // create a window for each other viewport in `viewport_output`, and
+        // destroy windows whose viewports are no longer present.
+        if let Some(output) = viewport_output.remove(&ViewportId::ROOT) {
+            egui_winit::process_viewport_commands(
+                &self.egui_ctx,
+                &mut self.viewport_info,
+                output.commands,
+                window,
+                &mut Default::default(),
+            );
+        }
+    }
+
     fn resize(&mut self, new_size: &PhysicalSize<u32>) {
         if let Err(err) = self.resize_swap_chain_and_render_target(
             new_size.width,
@@ -285,15 +311,12 @@ impl DemoApp {
         new_format: DXGI_FORMAT,
     ) -> windows::core::Result<()> {
         self.render_target.take();
-        unsafe {
-            self.swap_chain.ResizeBuffers(
-                2,
-                new_width,
-                new_height,
-                new_format,
-                DXGI_SWAP_CHAIN_FLAG(0),
-            )
-        }?;
+        self.egui_renderer.resize_swap_chain(
+            &self.swap_chain,
+            new_width,
+            new_height,
+            new_format,
+        )?;
         self.render_target
             .replace(Self::create_render_target_for_swap_chain(
                 &self.device,
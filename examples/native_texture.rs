@@ -81,7 +81,7 @@ impl App for DemoApp {
             &window,
             None,
             None,
-            None,
+            Some(egui_renderer.max_texture_dimension()),
         );
 
         // Image source: https://www.publicdomainpictures.net/en/view-image.php?image=308608
@@ -126,7 +126,7 @@ impl App for DemoApp {
         .unwrap();
 
         let tex = tex.unwrap();
-        let id = egui_renderer.register_native_texture(tex);
+        let id = egui_renderer.register_native_texture(tex).unwrap();
 
         Self {
             device,
@@ -170,8 +170,11 @@ impl DemoApp {
                     ui.add(image);
                 });
             });
-            let (renderer_output, platform_output, _) =
-                egui_directx11::split_output(egui_output);
+            let egui_directx11::SplitOutput {
+                renderer_output,
+                platform_output,
+                ..
+            } = egui_directx11::split_output(egui_output);
             self.egui_winit
                 .handle_platform_output(window, platform_output);
             unsafe {
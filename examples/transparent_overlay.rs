@@ -0,0 +1,325 @@
+//! A borderless, always-on-top window whose background is fully transparent,
+//! so that egui windows are blended over the desktop.
+//!
+//! The swap chain is created for composition with premultiplied alpha and
+//! presented through DirectComposition, which is what the desktop window
+//! manager expects for per-pixel transparency. The renderer outputs
+//! premultiplied colors and alpha with its default blend states, so the
+//! frame only has to be cleared to transparent black before rendering.
+
+use std::ptr;
+
+use windows::Win32::{
+    Foundation::{HMODULE, HWND},
+    Graphics::{
+        Direct3D::{D3D_DRIVER_TYPE_UNKNOWN, D3D_FEATURE_LEVEL_11_0},
+        Direct3D11::*,
+        DirectComposition::*,
+        Dxgi::{Common::*, *},
+    },
+};
+use windows::core::Interface;
+
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, EventLoop},
+    platform::windows::WindowAttributesExtWindows,
+    window::{Window, WindowAttributes, WindowId, WindowLevel},
+};
+
+fn main() {
+    let mut runner = AppRunner {
+        window_attributes: WindowAttributes::default()
+            .with_title("egui-directx11")
+            .with_inner_size(PhysicalSize::new(800, 600))
+            .with_decorations(false)
+            .with_transparent(true)
+            .with_window_level(WindowLevel::AlwaysOnTop)
+            // the window is drawn by DirectComposition only, so it does not
+            // need a GDI surface, which would be drawn below the visual.
+            .with_no_redirection_bitmap(true),
+        window: None,
+        app: None,
+    };
+    EventLoop::new().unwrap().run_app(&mut runner).unwrap();
+}
+
+struct OverlayApp {
+    device: ID3D11Device,
+    device_context: ID3D11DeviceContext,
+    swap_chain: IDXGISwapChain1,
+    render_target: Option<ID3D11RenderTargetView>,
+    // keep the composition tree alive as long as the swap chain is shown.
+    _composition: (
+        IDCompositionDevice,
+        IDCompositionTarget,
+        IDCompositionVisual,
+    ),
+    egui_ctx: egui::Context,
+    egui_renderer: egui_directx11::Renderer,
+    egui_winit: egui_winit::State,
+}
+
+impl OverlayApp {
+    fn new(window: &Window) -> Self {
+        use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+        let RawWindowHandle::Win32(window_handle) = window
+            .window_handle()
+            .expect("Failed to get window handle")
+            .as_raw()
+        else {
+            panic!("Unexpected RawWindowHandle variant");
+        };
+        let hwnd = HWND(window_handle.hwnd.get() as _);
+
+        let PhysicalSize { width, height } = window.inner_size();
+        let (device, device_context, swap_chain) =
+            Self::create_device_and_swap_chain(width, height)
+                .expect("Failed to create device and swap chain");
+        let composition = Self::create_composition(&device, &swap_chain, hwnd)
+            .expect("Failed to set up DirectComposition");
+        let render_target = Some(
+            Self::create_render_target_for_swap_chain(&device, &swap_chain)
+                .expect("Failed to create render target"),
+        );
+
+        let egui_ctx = egui::Context::default();
+        // make windows slightly translucent, so that the desktop shows
+        // through them as well.
+        egui_ctx.style_mut(|style| {
+            style.visuals.window_fill =
+                style.visuals.window_fill.gamma_multiply(0.85);
+        });
+        let egui_renderer = egui_directx11::Renderer::new(&device)
+            .expect("Failed to create egui renderer");
+        let egui_winit = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui_ctx.viewport_id(),
+            &window,
+            None,
+            None,
+            Some(egui_renderer.max_texture_dimension()),
+        );
+
+        Self {
+            device,
+            device_context,
+            swap_chain,
+            render_target,
+            _composition: composition,
+            egui_ctx,
+            egui_renderer,
+            egui_winit,
+        }
+    }
+
+    fn on_event(&mut self, window: &Window, event: &WindowEvent) {
+        let egui_response = self.egui_winit.on_window_event(window, event);
+        if !egui_response.consumed {
+            match event {
+                WindowEvent::Resized(new_size) => self.resize(new_size),
+                WindowEvent::RedrawRequested => self.render(window),
+                _ => (),
+            }
+        }
+    }
+
+    fn render(&mut self, window: &Window) {
+        let Some(render_target) = &self.render_target else {
+            return;
+        };
+        let egui_input = self.egui_winit.take_egui_input(window);
+        let egui_output = self.egui_ctx.run(egui_input, |ctx| {
+            egui::Window::new("Overlay").show(ctx, |ui| {
+                ui.label("The desktop shows through everywhere else.");
+                ui.label("Press Alt+F4 to close the overlay.");
+            });
+        });
+        let egui_directx11::SplitOutput {
+            renderer_output,
+            platform_output,
+            ..
+        } = egui_directx11::split_output(egui_output);
+        self.egui_winit
+            .handle_platform_output(window, platform_output);
+        unsafe {
+            // transparent black, as colors are premultiplied.
+            self.device_context
+                .ClearRenderTargetView(render_target, &[0.0; 4]);
+        }
+        let _ = self.egui_renderer.render(
+            &self.device_context,
+            render_target,
+            &self.egui_ctx,
+            renderer_output,
+        );
+        let _ = egui_directx11::Renderer::present(&self.swap_chain, 1, false);
+    }
+
+    fn resize(&mut self, new_size: &PhysicalSize<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+        self.render_target.take();
+        unsafe {
+            self.swap_chain.ResizeBuffers(
+                2,
+                new_size.width,
+                new_size.height,
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                DXGI_SWAP_CHAIN_FLAG(0),
+            )
+        }
+        .expect("Failed to resize swap chain");
+        self.render_target = Some(
+            Self::create_render_target_for_swap_chain(
+                &self.device,
+                &self.swap_chain,
+            )
+            .expect("Failed to create render target"),
+        );
+    }
+
+    fn create_device_and_swap_chain(
+        width: u32,
+        height: u32,
+    ) -> windows::core::Result<(
+        ID3D11Device,
+        ID3D11DeviceContext,
+        IDXGISwapChain1,
+    )> {
+        let dxgi_factory: IDXGIFactory2 =
+            unsafe { CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0)) }?;
+        let dxgi_adapter = unsafe { dxgi_factory.EnumAdapters(0) }?;
+
+        let mut device = None;
+        let mut device_context = None;
+        unsafe {
+            D3D11CreateDevice(
+                &dxgi_adapter,
+                D3D_DRIVER_TYPE_UNKNOWN,
+                HMODULE(ptr::null_mut()),
+                // DirectComposition requires BGRA support.
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                Some(&[D3D_FEATURE_LEVEL_11_0]),
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None,
+                Some(&mut device_context),
+            )
+        }?;
+        let device = device.unwrap();
+        let device_context = device_context.unwrap();
+
+        // composition swap chains must use the flip model, and only support
+        // premultiplied alpha for per-pixel transparency.
+        let swap_chain = unsafe {
+            dxgi_factory.CreateSwapChainForComposition(
+                &device,
+                &DXGI_SWAP_CHAIN_DESC1 {
+                    Width: width.max(1),
+                    Height: height.max(1),
+                    Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
+                    BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+                    BufferCount: 2,
+                    Scaling: DXGI_SCALING_STRETCH,
+                    SwapEffect: DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL,
+                    AlphaMode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+                    ..Default::default()
+                },
+                None,
+            )
+        }?;
+        Ok((device, device_context, swap_chain))
+    }
+
+    fn create_composition(
+        device: &ID3D11Device,
+        swap_chain: &IDXGISwapChain1,
+        hwnd: HWND,
+    ) -> windows::core::Result<(
+        IDCompositionDevice,
+        IDCompositionTarget,
+        IDCompositionVisual,
+    )> {
+        let dxgi_device: IDXGIDevice = device.cast()?;
+        let composition_device: IDCompositionDevice =
+            unsafe { DCompositionCreateDevice(&dxgi_device) }?;
+        let target =
+            unsafe { composition_device.CreateTargetForHwnd(hwnd, true) }?;
+        let visual = unsafe { composition_device.CreateVisual() }?;
+        unsafe {
+            visual.SetContent(swap_chain)?;
+            target.SetRoot(&visual)?;
+            composition_device.Commit()?;
+        }
+        Ok((composition_device, target, visual))
+    }
+
+    fn create_render_target_for_swap_chain(
+        device: &ID3D11Device,
+        swap_chain: &IDXGISwapChain1,
+    ) -> windows::core::Result<ID3D11RenderTargetView> {
+        let swap_chain_texture =
+            unsafe { swap_chain.GetBuffer::<ID3D11Texture2D>(0) }?;
+        let mut render_target = None;
+        unsafe {
+            device.CreateRenderTargetView(
+                &swap_chain_texture,
+                None,
+                Some(&mut render_target),
+            )
+        }?;
+        Ok(render_target.unwrap())
+    }
+}
+
+struct AppRunner {
+    window_attributes: WindowAttributes,
+    window: Option<Window>,
+    app: Option<OverlayApp>,
+}
+
+impl ApplicationHandler for AppRunner {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = event_loop
+            .create_window(self.window_attributes.clone())
+            .expect("Failed to create window");
+        self.app = Some(OverlayApp::new(&window));
+        self.window = Some(window);
+    }
+
+    fn suspended(&mut self, _: &ActiveEventLoop) {
+        self.app.take();
+        self.window.take();
+    }
+
+    fn about_to_wait(&mut self, _: &ActiveEventLoop) {
+        if let Some(window) = self.window.as_ref() {
+            window.request_redraw();
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        if let Some(window) = self.window.as_ref()
+            && window_id == window.id()
+        {
+            if event == WindowEvent::CloseRequested {
+                event_loop.exit()
+            } else if let Some(app) = self.app.as_mut() {
+                app.on_event(window, &event);
+            }
+        }
+    }
+}
@@ -1,3 +1,58 @@
+// keep in sync with `Constants` in `src/lib.rs`.
+cbuffer egui_constants: register(b0) {
+    // maps positions in points to normalized device coordinates.
+    row_major float4x4 g_projection;
+    // how colors are encoded for the render target, one of `OUTPUT_*`.
+    uint g_output_encoding;
+    // multiplies all output colors, which are premultiplied.
+    float g_opacity;
+    // the color space of vertex colors, one of `VERTEX_*`.
+    uint g_vertex_color_space;
+    // whether `g_color_matrix` is applied, see `filter_color`.
+    bool g_use_color_matrix;
+    row_major float4x4 g_color_matrix;
+};
+
+// keep in sync with `DrawConstants` in `src/lib.rs`.
+cbuffer egui_draw_constants: register(b1) {
+    // multiplies colors in linear space, for HDR output.
+    float g_emissive;
+    // written to id buffers, see `ps_egui_id`.
+    uint g_draw_id;
+    // the color space of the texture, one of `TEXTURE_*`.
+    uint g_texture_color_space;
+};
+
+// b2 is reserved for the constants of paint callbacks, see
+// `Renderer::set_callback_constants`.
+
+// keep in sync with `OutputEncoding` in `src/lib.rs`.
+// colors are written as is, i.e. in gamma space as egui expects.
+#define OUTPUT_GAMMA  0
+// colors are written in linear space, for sRGB-aware render targets, which
+// encode them to gamma space in hardware, and for scRGB.
+#define OUTPUT_LINEAR 1
+// colors are written with rec. 2020 primaries and the PQ curve, for HDR10.
+#define OUTPUT_PQ     2
+
+// keep in sync with `VertexColorSpace` in `src/lib.rs`.
+#define VERTEX_GAMMA  0
+#define VERTEX_LINEAR 1
+
+// keep in sync with `TextureColorSpace` in `src/texture.rs`.
+#define TEXTURE_SRGB         0
+#define TEXTURE_DISPLAY_P3   1
+#define TEXTURE_REC2020      2
+
+// mirrored for negative values, which encode colors outside of the rec. 709
+// gamut, as in scRGB.
+float3 gamma_from_linear(const float3 rgb) {
+    const float3 a  = abs(rgb);
+    const float3 lo = a * 12.92;
+    const float3 hi = 1.055 * pow(a, 1.0 / 2.4) - 0.055;
+    return sign(rgb) * (a <= 0.0031308 ? lo : hi);
+}
+
 void vs_egui(
     in const float2 i_pos  : POSITION,
     in const float2 i_uv   : TEXCOORD,
@@ -5,17 +60,213 @@ void vs_egui(
     out      float4 o_pos  : SV_POSITION,
     out      float2 o_uv   : TEXCOORD,
     out      float4 o_color: COLOR) {
-    o_pos   = float4(i_pos, 0.0, 1.0);
+    o_pos   = mul(g_projection, float4(i_pos, 0.0, 1.0));
     o_uv    = i_uv;
     o_color = i_color;
+    if (g_vertex_color_space == VERTEX_LINEAR && i_color.a > 0.0) {
+        // vertex colors are premultiplied, so convert the unmultiplied color.
+        o_color.rgb = gamma_from_linear(i_color.rgb / i_color.a) * i_color.a;
+    }
 }
 
-Texture2D<float4> g_texture: register(t0);
+// the slots of `g_texture` and `g_chroma`, which are moved by defining both,
+// see `Renderer::set_texture_slot`.
+#ifndef TEXTURE_SLOT
+#define TEXTURE_SLOT t0
+#define CHROMA_SLOT  t1
+#endif
+
+Texture2D<float4> g_texture: register(TEXTURE_SLOT);
 SamplerState      g_sampler: register(s0);
 
+float3 linear_from_gamma(const float3 rgb) {
+    const float3 a  = abs(rgb);
+    const float3 lo = a / 12.92;
+    const float3 hi = pow((a + 0.055) / 1.055, 2.4);
+    return sign(rgb) * (a <= 0.04045 ? lo : hi);
+}
+
+// convert an unmultiplied color of the texture to rec. 709 primaries, both in
+// gamma space. out of gamut colors are kept for linear and HDR10 output, and
+// clipped for regular SDR output.
+float3 rec709_from_texture(const float3 rgb) {
+    const float3x3 rec709_from_p3 = {
+         1.2249, -0.2247,  0.0000,
+        -0.0420,  1.0419,  0.0000,
+        -0.0197, -0.0786,  1.0979,
+    };
+    const float3x3 rec709_from_rec2020 = {
+         1.6605, -0.5876, -0.0728,
+        -0.1246,  1.1329, -0.0083,
+        -0.0182, -0.1006,  1.1187,
+    };
+    float3 result;
+    switch (g_texture_color_space) {
+    case TEXTURE_DISPLAY_P3:
+        result = gamma_from_linear(mul(rec709_from_p3, linear_from_gamma(rgb)));
+        break;
+    case TEXTURE_REC2020:
+        result = gamma_from_linear(
+            mul(rec709_from_rec2020, linear_from_gamma(rgb)));
+        break;
+    default:
+        return rgb;
+    }
+    return g_output_encoding == OUTPUT_GAMMA ? saturate(result) : result;
+}
+
+// like `rec709_from_texture`, for premultiplied colors.
+float4 rec709_from_texture_premultiplied(const float4 color) {
+    if (g_texture_color_space == TEXTURE_SRGB || color.a <= 0.0) {
+        return color;
+    }
+    return float4(rec709_from_texture(color.rgb / color.a) * color.a, color.a);
+}
+
+// the SDR white of egui maps to 80 nits, as in scRGB.
+float3 pq_from_linear(const float3 rgb) {
+    const float3x3 rec2020_from_rec709 = {
+        0.6274, 0.3293, 0.0433,
+        0.0691, 0.9195, 0.0114,
+        0.0164, 0.0880, 0.8956,
+    };
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    const float3 y = pow(
+        max(mul(rec2020_from_rec709, rgb) * (80.0 / 10000.0), 0.0), m1);
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), m2);
+}
+
+// encode an unmultiplied color in gamma space for the render target.
+float3 encode_output(const float3 rgb) {
+    switch (g_output_encoding) {
+    case OUTPUT_LINEAR: return linear_from_gamma(rgb) * g_emissive;
+    case OUTPUT_PQ:     return pq_from_linear(linear_from_gamma(rgb) * g_emissive);
+    default:            return rgb;
+    }
+}
+
+// apply the color matrix to an unmultiplied color in gamma space. the matrix
+// is applied in linear space.
+float4 filter_color(const float4 color) {
+    if (!g_use_color_matrix) {
+        return color;
+    }
+    const float4 c =
+        mul(g_color_matrix, float4(linear_from_gamma(color.rgb), color.a));
+    return float4(gamma_from_linear(c.rgb), saturate(c.a));
+}
+
+float4 output_color(float4 color) {
+    if (g_use_color_matrix && color.a > 0.0) {
+        const float4 c = filter_color(float4(color.rgb / color.a, color.a));
+        color = float4(c.rgb * c.a, c.a);
+    }
+    if (g_output_encoding != OUTPUT_GAMMA && color.a > 0.0) {
+        // colors are premultiplied, so convert the unmultiplied color.
+        color.rgb = encode_output(color.rgb / color.a) * color.a;
+    }
+    return color * g_opacity;
+}
+
+// the mip level of textures is selected by the sampler, whose LOD range is
+// limited to the largest level by default, see `SamplerOptions::max_lod`.
 float4 ps_egui(
     in const float4 i_pos  : SV_POSITION,
     in const float2 i_uv   : TEXCOORD,
     in const float4 i_color: COLOR): SV_TARGET {
-    return i_color * g_texture.SampleLevel(g_sampler, i_uv, 0);
+    const float4 texel = g_texture.Sample(g_sampler, i_uv);
+    return output_color(i_color * rec709_from_texture_premultiplied(texel));
+}
+
+// for textures with straight alpha, drawn with a blend state that multiplies
+// colors by alpha. so colors are output unmultiplied.
+float4 ps_egui_straight(
+    in const float4 i_pos  : SV_POSITION,
+    in const float2 i_uv   : TEXCOORD,
+    in const float4 i_color: COLOR): SV_TARGET {
+    const float4 texel = g_texture.Sample(g_sampler, i_uv);
+    // the vertex color is premultiplied as all colors in egui.
+    const float3 tint = i_color.a > 0.0 ? i_color.rgb / i_color.a : 0.0;
+    const float4 color = filter_color(
+        float4(rec709_from_texture(texel.rgb) * tint, texel.a * i_color.a));
+    return float4(encode_output(color.rgb), color.a * g_opacity);
+}
+
+// for textures with straight alpha drawn with `BlendMode::Multiply`, whose
+// blend state expects premultiplied colors.
+float4 ps_egui_straight_premultiplied(
+    in const float4 i_pos  : SV_POSITION,
+    in const float2 i_uv   : TEXCOORD,
+    in const float4 i_color: COLOR): SV_TARGET {
+    const float4 color = ps_egui_straight(i_pos, i_uv, i_color);
+    return float4(color.rgb * color.a, color.a);
+}
+
+// for single-channel coverage masks, which tint the vertex color by the red
+// channel of the texture.
+float4 ps_egui_mask(
+    in const float4 i_pos  : SV_POSITION,
+    in const float2 i_uv   : TEXCOORD,
+    in const float4 i_color: COLOR): SV_TARGET {
+    return output_color(i_color * g_texture.Sample(g_sampler, i_uv).r);
+}
+
+// the luma plane of video textures is bound as `g_texture`.
+Texture2D<float2> g_chroma: register(CHROMA_SLOT);
+
+// BT.709 with limited range, as used by most HD video.
+float3 rgb_from_yuv(const float y, const float2 uv) {
+    const float  l  = (y  - 16.0 / 255.0) * (255.0 / 219.0);
+    const float2 c  = (uv - 128.0 / 255.0) * (255.0 / 224.0);
+    return saturate(float3(
+        l + 1.5748 * c.y,
+        l - 0.1873 * c.x - 0.4681 * c.y,
+        l + 1.8556 * c.x));
+}
+
+float4 ps_egui_nv12(
+    in const float4 i_pos  : SV_POSITION,
+    in const float2 i_uv   : TEXCOORD,
+    in const float4 i_color: COLOR): SV_TARGET {
+    const float  y  = g_texture.SampleLevel(g_sampler, i_uv, 0).r;
+    const float2 uv = g_chroma.SampleLevel(g_sampler, i_uv, 0);
+    return output_color(i_color * float4(rgb_from_yuv(y, uv), 1.0));
+}
+
+// writes the id of the draw to an id buffer wherever the mesh is mostly
+// opaque, so that anti-aliased edges and shadows are not picked.
+uint ps_egui_id(
+    in const float4 i_pos  : SV_POSITION,
+    in const float2 i_uv   : TEXCOORD,
+    in const float4 i_color: COLOR): SV_TARGET {
+    if (i_color.a * g_texture.SampleLevel(g_sampler, i_uv, 0).a < 0.5) {
+        discard;
+    }
+    return g_draw_id;
+}
+
+// a full-screen triangle, with uv covering the screen by 0..1.
+void vs_blit(
+    in const uint   i_id : SV_VERTEXID,
+    out      float4 o_pos: SV_POSITION,
+    out      float2 o_uv : TEXCOORD) {
+    o_uv  = float2((i_id << 1) & 2, i_id & 2);
+    o_pos = float4(o_uv * float2(2.0, -2.0) + float2(-1.0, 1.0), 0.0, 1.0);
+}
+
+// four bilinear taps a quarter of a destination pixel away from its center,
+// which is an exact box filter for 2x and 4x downscaling.
+float4 ps_blit_downscale(
+    in const float4 i_pos: SV_POSITION,
+    in const float2 i_uv : TEXCOORD): SV_TARGET {
+    const float2 d = float2(ddx(i_uv).x, ddy(i_uv).y) * 0.25;
+    return 0.25 * (
+        g_texture.SampleLevel(g_sampler, i_uv + float2(-d.x, -d.y), 0) +
+        g_texture.SampleLevel(g_sampler, i_uv + float2( d.x, -d.y), 0) +
+        g_texture.SampleLevel(g_sampler, i_uv + float2(-d.x,  d.y), 0) +
+        g_texture.SampleLevel(g_sampler, i_uv + float2( d.x,  d.y), 0));
 }
@@ -0,0 +1,69 @@
+use windows::Win32::Graphics::{Direct3D::*, Direct3D11::*};
+use windows::core::Result;
+
+use crate::shader;
+
+/// Shaders for copying a texture to a render target with a full-screen
+/// triangle. Created on first use, as most applications never blit.
+#[derive(Clone)]
+pub struct Blitter {
+    vertex_shader: ID3D11VertexShader,
+    downscale_shader: ID3D11PixelShader,
+}
+
+impl Blitter {
+    pub fn new(device: &ID3D11Device) -> Result<Self> {
+        let vs_blob = shader::VS_BLIT.bytecode()?;
+        let ps_blob = shader::PS_BLIT_DOWNSCALE.bytecode()?;
+        let mut vertex_shader = None;
+        let mut downscale_shader = None;
+        unsafe {
+            device.CreateVertexShader(
+                &vs_blob,
+                None,
+                Some(&mut vertex_shader),
+            )?;
+            device.CreatePixelShader(
+                &ps_blob,
+                None,
+                Some(&mut downscale_shader),
+            )?;
+        }
+        Ok(Self {
+            vertex_shader: vertex_shader.unwrap(),
+            downscale_shader: downscale_shader.unwrap(),
+        })
+    }
+
+    pub fn downscale(
+        &self,
+        ctx: &ID3D11DeviceContext,
+        sampler: &ID3D11SamplerState,
+        src: &ID3D11ShaderResourceView,
+        dst: &ID3D11RenderTargetView,
+        dst_size: (u32, u32),
+    ) {
+        unsafe {
+            ctx.IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            ctx.IASetInputLayout(None);
+            ctx.VSSetShader(&self.vertex_shader, None);
+            ctx.PSSetShader(&self.downscale_shader, None);
+            ctx.RSSetState(None);
+            ctx.RSSetViewports(Some(&[D3D11_VIEWPORT {
+                TopLeftX: 0.,
+                TopLeftY: 0.,
+                Width: dst_size.0 as _,
+                Height: dst_size.1 as _,
+                MinDepth: 0.,
+                MaxDepth: 1.,
+            }]));
+            ctx.PSSetShaderResources(0, Some(&[Some(src.clone())]));
+            ctx.PSSetSamplers(0, Some(&[Some(sampler.clone())]));
+            ctx.OMSetRenderTargets(Some(&[Some(dst.clone())]), None);
+            ctx.OMSetBlendState(None, None, u32::MAX);
+            ctx.Draw(3, 0);
+            // unbind the source, so that it can be rendered to again.
+            ctx.PSSetShaderResources(0, Some(&[None]));
+        }
+    }
+}
@@ -0,0 +1,459 @@
+// Optional loading of DDS files into native textures, for tools that ship
+// their images pre-compressed.
+
+use windows::Win32::{
+    Foundation::E_INVALIDARG,
+    Graphics::{Direct3D11::*, Dxgi::Common::*},
+};
+use windows::core::{Error, Result};
+
+use egui::TextureId;
+
+use crate::{AlphaMode, NativeTextureOptions, Renderer};
+
+const MAGIC: &[u8; 4] = b"DDS ";
+const HEADER_SIZE: usize = 124;
+const DX10_HEADER_SIZE: usize = 20;
+
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_VOLUME: u32 = 0x200000;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDS_DIMENSION_TEXTURE2D: u32 = 3;
+const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+const DDS_ALPHA_MODE_PREMULTIPLIED: u32 = 2;
+const DDS_ALPHA_MODE_OPAQUE: u32 = 3;
+
+/// The parts of a DDS file needed for creating a texture.
+struct DdsImage<'a> {
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+    format: DXGI_FORMAT,
+    alpha_mode: AlphaMode,
+    // the offset into `data` and the row pitch of each mip level.
+    mips: Vec<(usize, usize)>,
+    data: &'a [u8],
+}
+
+impl Renderer {
+    /// Create an immutable texture from the content of a DDS file, and
+    /// register it for use in egui like [`Renderer::register_native_texture`].
+    ///
+    /// All mip levels stored in the file are uploaded, and sampled by setting
+    /// [`SamplerOptions::max_lod`](crate::SamplerOptions::max_lod) of the
+    /// default options to [`f32::MAX`] for files with mipmaps. Supported are
+    /// 2D
+    /// textures in the block-compressed formats BC1 to BC7 (`DXT1` to `DXT5`,
+    /// `ATI1` and `ATI2` in legacy headers), and the common uncompressed
+    /// formats with 8-bit (RGBA, BGRA, BGRX, R and RG), 10-bit, 16-bit float
+    /// and 32-bit float channels. sRGB formats are viewed as their UNORM
+    /// counterparts, as `egui` expects colors in gamma space. Colors are drawn
+    /// with [`AlphaMode::Straight`], unless the file declares them to be
+    /// premultiplied (`DXT2`, `DXT4` or the alpha mode of the DX10 header) or
+    /// opaque.
+    ///
+    /// This function fails if the file is malformed, if it contains a cube
+    /// map, a texture array or a volume texture, or if its format is not
+    /// supported. Only available with the `dds` feature.
+    pub fn register_dds(&mut self, bytes: &[u8]) -> Result<TextureId> {
+        let image = DdsImage::parse(bytes)?;
+        let max_dimension = self.max_texture_dimension();
+        if image.width as usize > max_dimension
+            || image.height as usize > max_dimension
+        {
+            return Err(invalid(format!(
+                "texture size {}x{} exceeds the maximum texture dimension {max_dimension}",
+                image.width, image.height,
+            )));
+        }
+
+        let subresource_data = image
+            .mips
+            .iter()
+            .map(|&(offset, pitch)| D3D11_SUBRESOURCE_DATA {
+                pSysMem: image.data[offset..].as_ptr() as _,
+                SysMemPitch: pitch as _,
+                SysMemSlicePitch: 0,
+            })
+            .collect::<Vec<_>>();
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: image.width,
+            Height: image.height,
+            MipLevels: image.mip_levels,
+            ArraySize: 1,
+            Format: image.format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_IMMUTABLE,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
+            ..Default::default()
+        };
+        let mut tex = None;
+        unsafe {
+            self.device.CreateTexture2D(
+                &desc,
+                Some(subresource_data.as_ptr()),
+                Some(&mut tex),
+            )
+        }
+        .map_err(|err| {
+            Error::new(
+                err.code(),
+                format!(
+                    "failed to create a texture for the DDS file: {}",
+                    err.message()
+                ),
+            )
+        })?;
+        let mut options = self.lock_texture_pool().default_native_options();
+        if image.mip_levels > 1 {
+            // sample the stored mip levels, instead of aliasing by only
+            // sampling the largest one when minifying.
+            options.sampler.max_lod = f32::MAX;
+        }
+        self.register_native_texture_with_format(
+            tex.unwrap(),
+            image.format,
+            NativeTextureOptions {
+                alpha_mode: image.alpha_mode,
+                ..options
+            },
+        )
+    }
+}
+
+impl<'a> DdsImage<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < MAGIC.len() + HEADER_SIZE
+            || &bytes[..MAGIC.len()] != MAGIC
+        {
+            return Err(invalid("the data is not a DDS file".into()));
+        }
+        let header = &bytes[MAGIC.len()..];
+        let field = |offset: usize| read_u32(header, offset);
+        if field(0) as usize != HEADER_SIZE {
+            return Err(invalid("the DDS header is malformed".into()));
+        }
+        let flags = field(4);
+        let height = field(8);
+        let width = field(12);
+        let mip_levels = if flags & DDSD_MIPMAPCOUNT != 0 {
+            field(24).max(1)
+        } else {
+            1
+        };
+        let pixel_flags = field(76);
+        let four_cc = &header[80..84];
+        let caps2 = field(108);
+        if caps2 & (DDSCAPS2_CUBEMAP | DDSCAPS2_VOLUME) != 0 {
+            return Err(invalid(
+                "only 2D textures are supported, not cube maps or volumes"
+                    .into(),
+            ));
+        }
+
+        let mut data = &bytes[MAGIC.len() + HEADER_SIZE..];
+        let mut alpha_mode = AlphaMode::Straight;
+        let format = if pixel_flags & DDPF_FOURCC != 0 && four_cc == b"DX10" {
+            if data.len() < DX10_HEADER_SIZE {
+                return Err(invalid("the DX10 header is truncated".into()));
+            }
+            let format = DXGI_FORMAT(read_u32(data, 0) as _);
+            let dimension = read_u32(data, 4);
+            let misc_flag = read_u32(data, 8);
+            let array_size = read_u32(data, 12);
+            let misc_flags2 = read_u32(data, 16);
+            if dimension != DDS_DIMENSION_TEXTURE2D
+                || misc_flag & DDS_RESOURCE_MISC_TEXTURECUBE != 0
+                || array_size > 1
+            {
+                return Err(invalid(
+                    "only 2D textures are supported, not cube maps, arrays or volumes"
+                        .into(),
+                ));
+            }
+            if matches!(
+                misc_flags2 & 0x7,
+                DDS_ALPHA_MODE_PREMULTIPLIED | DDS_ALPHA_MODE_OPAQUE
+            ) {
+                alpha_mode = AlphaMode::Premultiplied;
+            }
+            data = &data[DX10_HEADER_SIZE..];
+            format
+        } else if pixel_flags & DDPF_FOURCC != 0 {
+            if matches!(four_cc, b"DXT2" | b"DXT4") {
+                alpha_mode = AlphaMode::Premultiplied;
+            }
+            match four_cc {
+                b"DXT1" => DXGI_FORMAT_BC1_UNORM,
+                b"DXT2" | b"DXT3" => DXGI_FORMAT_BC2_UNORM,
+                b"DXT4" | b"DXT5" => DXGI_FORMAT_BC3_UNORM,
+                b"ATI1" | b"BC4U" => DXGI_FORMAT_BC4_UNORM,
+                b"BC4S" => DXGI_FORMAT_BC4_SNORM,
+                b"ATI2" | b"BC5U" => DXGI_FORMAT_BC5_UNORM,
+                b"BC5S" => DXGI_FORMAT_BC5_SNORM,
+                _ => DXGI_FORMAT_UNKNOWN,
+            }
+        } else if pixel_flags & DDPF_RGB != 0 && field(84) == 32 {
+            match (field(88), field(92), field(96), field(100)) {
+                (0xff, 0xff00, 0xff0000, 0xff000000) => {
+                    DXGI_FORMAT_R8G8B8A8_UNORM
+                },
+                (0xff0000, 0xff00, 0xff, 0xff000000) => {
+                    DXGI_FORMAT_B8G8R8A8_UNORM
+                },
+                (0xff0000, 0xff00, 0xff, 0) => {
+                    alpha_mode = AlphaMode::Premultiplied;
+                    DXGI_FORMAT_B8G8R8X8_UNORM
+                },
+                _ => DXGI_FORMAT_UNKNOWN,
+            }
+        } else {
+            DXGI_FORMAT_UNKNOWN
+        };
+        let format = unorm_from_srgb(format);
+        if block_size(format).is_none() && pixel_size(format).is_none() {
+            return Err(invalid(format!(
+                "the format {format:?} of the DDS file is not supported"
+            )));
+        }
+
+        if width == 0 || height == 0 {
+            return Err(invalid("the DDS file is empty".into()));
+        }
+        // no device supports larger textures, which also keeps the sizes
+        // below from overflowing.
+        if width > D3D11_REQ_TEXTURE2D_U_OR_V_DIMENSION
+            || height > D3D11_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        {
+            return Err(invalid(format!(
+                "the texture size {width}x{height} exceeds the maximum texture dimension {D3D11_REQ_TEXTURE2D_U_OR_V_DIMENSION}"
+            )));
+        }
+        if mip_levels > u32::BITS - width.max(height).leading_zeros() {
+            return Err(invalid(format!(
+                "{mip_levels} mip levels are too many for a {width}x{height} texture"
+            )));
+        }
+        let mut mips = Vec::with_capacity(mip_levels as _);
+        let mut size = 0usize;
+        for mip in 0..mip_levels {
+            let layout = mip_layout(format, width, height, mip).and_then(
+                |(pitch, rows)| {
+                    Some((pitch, size.checked_add(pitch.checked_mul(rows)?)?))
+                },
+            );
+            let Some((pitch, end)) = layout else {
+                return Err(invalid("the DDS file is too large".into()));
+            };
+            mips.push((size, pitch));
+            size = end;
+        }
+        if data.len() < size {
+            return Err(invalid(format!(
+                "the DDS file is truncated, expected {size} bytes of pixel data, got {}",
+                data.len()
+            )));
+        }
+        Ok(Self {
+            width,
+            height,
+            mip_levels,
+            format,
+            alpha_mode,
+            mips,
+            data,
+        })
+    }
+}
+
+/// The pitch in bytes and the number of rows of a mip level, where a row of a
+/// block-compressed format is a row of 4x4 blocks, or `None` on overflow.
+fn mip_layout(
+    format: DXGI_FORMAT,
+    width: u32,
+    height: u32,
+    mip: u32,
+) -> Option<(usize, usize)> {
+    let width = (width >> mip).max(1) as usize;
+    let height = (height >> mip).max(1) as usize;
+    match block_size(format) {
+        Some(block_size) => Some((
+            width.div_ceil(4).checked_mul(block_size)?,
+            height.div_ceil(4),
+        )),
+        None => Some((width.checked_mul(pixel_size(format)?)?, height)),
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(E_INVALIDARG, message)
+}
+
+fn unorm_from_srgb(format: DXGI_FORMAT) -> DXGI_FORMAT {
+    match format {
+        DXGI_FORMAT_BC1_UNORM_SRGB => DXGI_FORMAT_BC1_UNORM,
+        DXGI_FORMAT_BC2_UNORM_SRGB => DXGI_FORMAT_BC2_UNORM,
+        DXGI_FORMAT_BC3_UNORM_SRGB => DXGI_FORMAT_BC3_UNORM,
+        DXGI_FORMAT_BC7_UNORM_SRGB => DXGI_FORMAT_BC7_UNORM,
+        DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => DXGI_FORMAT_R8G8B8A8_UNORM,
+        DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => DXGI_FORMAT_B8G8R8A8_UNORM,
+        DXGI_FORMAT_B8G8R8X8_UNORM_SRGB => DXGI_FORMAT_B8G8R8X8_UNORM,
+        format => format,
+    }
+}
+
+/// The size in bytes of a 4x4 block of a block-compressed format.
+fn block_size(format: DXGI_FORMAT) -> Option<usize> {
+    match format {
+        DXGI_FORMAT_BC1_UNORM
+        | DXGI_FORMAT_BC4_UNORM
+        | DXGI_FORMAT_BC4_SNORM => Some(8),
+        DXGI_FORMAT_BC2_UNORM
+        | DXGI_FORMAT_BC3_UNORM
+        | DXGI_FORMAT_BC5_UNORM
+        | DXGI_FORMAT_BC5_SNORM
+        | DXGI_FORMAT_BC6H_UF16
+        | DXGI_FORMAT_BC6H_SF16
+        | DXGI_FORMAT_BC7_UNORM => Some(16),
+        _ => None,
+    }
+}
+
+/// The size in bytes of a pixel of an uncompressed format.
+fn pixel_size(format: DXGI_FORMAT) -> Option<usize> {
+    match format {
+        DXGI_FORMAT_R8_UNORM => Some(1),
+        DXGI_FORMAT_R8G8_UNORM => Some(2),
+        DXGI_FORMAT_R8G8B8A8_UNORM
+        | DXGI_FORMAT_B8G8R8A8_UNORM
+        | DXGI_FORMAT_B8G8R8X8_UNORM
+        | DXGI_FORMAT_R10G10B10A2_UNORM => Some(4),
+        DXGI_FORMAT_R16G16B16A16_FLOAT => Some(8),
+        DXGI_FORMAT_R32G32B32A32_FLOAT => Some(16),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A DDS file of `width`x`height` with `mip_levels` mip levels and the
+    /// four-character code `four_cc`, followed by `data`.
+    fn dds(
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        four_cc: &[u8; 4],
+        caps2: u32,
+        data: &[u8],
+    ) -> Vec<u8> {
+        let mut header = [0u32; HEADER_SIZE / 4];
+        header[0] = HEADER_SIZE as _;
+        header[1] = DDSD_MIPMAPCOUNT;
+        header[2] = height;
+        header[3] = width;
+        header[6] = mip_levels;
+        header[18] = 32;
+        header[19] = DDPF_FOURCC;
+        header[20] = u32::from_le_bytes(*four_cc);
+        header[27] = caps2;
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(header.iter().flat_map(|field| field.to_le_bytes()));
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// A DX10 header for a 2D texture of `format`.
+    fn dx10(format: DXGI_FORMAT, misc_flag: u32, misc_flags2: u32) -> Vec<u8> {
+        [
+            format.0 as u32,
+            DDS_DIMENSION_TEXTURE2D,
+            misc_flag,
+            1,
+            misc_flags2,
+        ]
+        .iter()
+        .flat_map(|field| field.to_le_bytes())
+        .collect()
+    }
+
+    #[test]
+    fn parses_dxt1_with_mip_levels() {
+        // 2x2, then 1x1 blocks of 8 bytes for 8x8, 4x4, 2x2 and 1x1.
+        let bytes = dds(8, 8, 4, b"DXT1", 0, &[0; 32 + 8 * 3]);
+        let image = DdsImage::parse(&bytes).unwrap();
+        assert_eq!((image.width, image.height), (8, 8));
+        assert_eq!(image.mip_levels, 4);
+        assert_eq!(image.format, DXGI_FORMAT_BC1_UNORM);
+        assert_eq!(image.alpha_mode, AlphaMode::Straight);
+        assert_eq!(image.mips, [(0, 16), (32, 8), (40, 8), (48, 8)]);
+    }
+
+    #[test]
+    fn parses_dxt5_and_premultiplied_dxt4() {
+        let bytes = dds(4, 4, 1, b"DXT5", 0, &[0; 16]);
+        let image = DdsImage::parse(&bytes).unwrap();
+        assert_eq!(image.format, DXGI_FORMAT_BC3_UNORM);
+        assert_eq!(image.alpha_mode, AlphaMode::Straight);
+        let bytes = dds(4, 4, 1, b"DXT4", 0, &[0; 16]);
+        let image = DdsImage::parse(&bytes).unwrap();
+        assert_eq!(image.alpha_mode, AlphaMode::Premultiplied);
+    }
+
+    #[test]
+    fn parses_dx10_bc7_as_unorm() {
+        let mut data =
+            dx10(DXGI_FORMAT_BC7_UNORM_SRGB, 0, DDS_ALPHA_MODE_PREMULTIPLIED);
+        // 3x2 blocks of 16 bytes.
+        data.extend_from_slice(&[0; 96]);
+        let bytes = dds(12, 5, 1, b"DX10", 0, &data);
+        let image = DdsImage::parse(&bytes).unwrap();
+        assert_eq!(image.format, DXGI_FORMAT_BC7_UNORM);
+        assert_eq!(image.alpha_mode, AlphaMode::Premultiplied);
+        assert_eq!(image.data.len(), 96);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let bytes = dds(8, 8, 1, b"DXT1", 0, &[0; 32]);
+        assert!(DdsImage::parse(&bytes).is_ok());
+        assert!(DdsImage::parse(&bytes[..bytes.len() - 1]).is_err());
+        // the header itself, and the DX10 header.
+        assert!(DdsImage::parse(&bytes[..100]).is_err());
+        assert!(DdsImage::parse(&dds(4, 4, 1, b"DX10", 0, &[0; 19])).is_err());
+    }
+
+    #[test]
+    fn rejects_cube_maps() {
+        let bytes = dds(4, 4, 1, b"DXT1", DDSCAPS2_CUBEMAP, &[0; 8 * 6]);
+        assert!(DdsImage::parse(&bytes).is_err());
+        let mut data =
+            dx10(DXGI_FORMAT_BC1_UNORM, DDS_RESOURCE_MISC_TEXTURECUBE, 0);
+        data.extend_from_slice(&[0; 8 * 6]);
+        assert!(DdsImage::parse(&dds(4, 4, 1, b"DX10", 0, &data)).is_err());
+    }
+
+    #[test]
+    fn rejects_too_many_mip_levels() {
+        // 4x4, 2x2 and 1x1 at most.
+        assert!(DdsImage::parse(&dds(4, 4, 3, b"DXT1", 0, &[0; 24])).is_ok());
+        assert!(DdsImage::parse(&dds(4, 4, 4, b"DXT1", 0, &[0; 32])).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_sizes_without_overflowing() {
+        let data = dx10(DXGI_FORMAT_R32G32B32A32_FLOAT, 0, 0);
+        let bytes = dds(u32::MAX, u32::MAX, 32, b"DX10", 0, &data);
+        assert!(DdsImage::parse(&bytes).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+// Optional access to the messages of the Direct3D11 debug layer, for
+// diagnosing invalid pipeline state.
+
+use std::mem;
+
+use windows::Win32::Graphics::Direct3D11::*;
+use windows::core::Interface;
+
+use crate::Renderer;
+
+impl Renderer {
+    /// Take all messages stored by the Direct3D11 debug layer of the device
+    /// of this [`Renderer`], formatted as `"{severity}: {description}"`, and
+    /// clear them from its message queue.
+    ///
+    /// The debug layer is only active if the device has been created with
+    /// `D3D11_CREATE_DEVICE_DEBUG`, which requires the Graphics Tools of
+    /// Windows to be installed. Otherwise, this function returns no messages.
+    /// Note that the message queue is shared by everything using the device,
+    /// so the messages are not limited to those caused by this crate.
+    ///
+    /// Only available with the `debug-layer` feature, which should be
+    /// disabled for release builds.
+    pub fn drain_debug_messages(&self) -> Vec<String> {
+        let Ok(info_queue) = self.device.cast::<ID3D11InfoQueue>() else {
+            return Vec::new();
+        };
+        let mut messages = Vec::new();
+        unsafe {
+            for i in
+                0..info_queue.GetNumStoredMessagesAllowedByRetrievalFilter()
+            {
+                let mut len = 0;
+                if info_queue.GetMessage(i, None, &mut len).is_err() {
+                    continue;
+                }
+                // the description is stored right after the message, so
+                // allocate enough aligned memory for both.
+                let mut buffer =
+                    vec![
+                        mem::MaybeUninit::<D3D11_MESSAGE>::uninit();
+                        len.div_ceil(mem::size_of::<D3D11_MESSAGE>())
+                    ];
+                let message = buffer.as_mut_ptr().cast::<D3D11_MESSAGE>();
+                if info_queue.GetMessage(i, Some(message), &mut len).is_err() {
+                    continue;
+                }
+                let message = &*message;
+                let description = std::slice::from_raw_parts(
+                    message.pDescription,
+                    message.DescriptionByteLength,
+                );
+                let description = String::from_utf8_lossy(description);
+                messages.push(format!(
+                    "{}: {}",
+                    severity_name(message.Severity),
+                    description.trim_end_matches('\0'),
+                ));
+            }
+            info_queue.ClearStoredMessages();
+        }
+        messages
+    }
+}
+
+fn severity_name(severity: D3D11_MESSAGE_SEVERITY) -> &'static str {
+    match severity {
+        D3D11_MESSAGE_SEVERITY_CORRUPTION => "corruption",
+        D3D11_MESSAGE_SEVERITY_ERROR => "error",
+        D3D11_MESSAGE_SEVERITY_WARNING => "warning",
+        D3D11_MESSAGE_SEVERITY_INFO => "info",
+        _ => "message",
+    }
+}
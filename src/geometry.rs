@@ -0,0 +1,758 @@
+use std::mem;
+
+use egui::{
+    ClippedPrimitive, Pos2, Rect, TextureId,
+    epaint::{Mesh, Primitive, Vertex},
+};
+
+use windows::Win32::{
+    Foundation::E_INVALIDARG,
+    Graphics::{Direct3D11::*, Dxgi::Common::*},
+};
+use windows::core::{Error, Result};
+
+/// The size of resources every device supports. Larger buffers may fail to be
+/// created, depending on the memory of the adapter.
+const GUARANTEED_RESOURCE_SIZE: usize =
+    D3D11_REQ_RESOURCE_SIZE_IN_MEGABYTES_EXPRESSION_A_TERM as usize
+        * 1024
+        * 1024;
+
+// keep in sync with `Renderer::INPUT_ELEMENTS_DESC` and `VERTEX_STRIDE`.
+#[repr(C)]
+pub struct VertexData {
+    pos: Pos2,
+    uv: Pos2,
+    color: [f32; 4],
+}
+
+/// The size in bytes of a vertex passed to
+/// [`crate::Renderer::render_raw`], i.e. of [`egui::epaint::Vertex`].
+pub const RAW_VERTEX_SIZE: usize = 20;
+
+const _: () = assert!(mem::size_of::<Vertex>() == RAW_VERTEX_SIZE);
+
+/// A draw call into raw geometry, for [`crate::Renderer::render_raw`]. The
+/// layout is that of the C struct
+///
+/// ```c
+/// struct RawDrawCommand {
+///     uint64_t texture_id;
+///     uint32_t texture_is_user;
+///     float    clip_rect[4];
+///     uint32_t index_offset;
+///     uint32_t index_count;
+/// };
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RawDrawCommand {
+    /// The id of the texture to sample, i.e. the value of
+    /// [`TextureId::Managed`] or [`TextureId::User`].
+    pub texture_id: u64,
+    /// `0` for textures managed by `egui`, i.e. [`TextureId::Managed`], and
+    /// any other value for native textures, i.e. [`TextureId::User`].
+    pub texture_is_user: u32,
+    /// The clip rectangle in points, as `[min_x, min_y, max_x, max_y]`.
+    pub clip_rect: [f32; 4],
+    /// The index of the first index of the draw in the index buffer.
+    pub index_offset: u32,
+    /// The number of indices of the draw, a multiple of 3.
+    pub index_count: u32,
+}
+
+/// A draw call into the geometry of a [`GeometryWriter`].
+pub struct DrawCommand {
+    pub texture_id: TextureId,
+    pub clip_rect: Rect,
+    // the bounds of the vertices, in points.
+    pub bounds: Rect,
+    pub first_index: u32,
+    pub index_count: u32,
+    pub base_vertex: i32,
+    pub draw_id: u32,
+}
+
+struct DynamicBuffer {
+    buffer: ID3D11Buffer,
+    capacity: usize,
+    // whether the buffer has been mapped since it was created, see `upload`.
+    written: bool,
+    // the number of uploads in a row below the ratio of the shrink policy.
+    underused: usize,
+}
+
+/// The buffers of one slot of the ring of a [`GeometryWriter`].
+#[derive(Default)]
+struct BufferSet {
+    vertex: Option<DynamicBuffer>,
+    index: Option<DynamicBuffer>,
+}
+
+/// Geometry of `egui` primitives packed into a single vertex buffer and a
+/// single index buffer, for drawing with [`crate::Renderer::draw_appended`].
+///
+/// Primitives are appended with [`crate::Renderer::append_geometry`], possibly
+/// from several `egui` contexts, and are drawn in the order they have been
+/// appended. The writer keeps its content until [`GeometryWriter::clear`] is
+/// called, so the same geometry may be drawn multiple times. Its buffers are
+/// uploaded to the GPU on the first draw after a modification, and reused
+/// afterwards. They grow to fit the largest geometry drawn with the writer,
+/// and are only shrunk as configured with
+/// [`GeometryWriter::set_shrink_policy`]. See
+/// [`GeometryWriter::set_frames_in_flight`] for rotating between several
+/// sets of buffers.
+///
+/// The GPU buffers are created on the device of the [`crate::Renderer`]
+/// drawing with the writer, so a writer should only be used with one
+/// [`crate::Renderer`], or with [`crate::Renderer`]s on the same device.
+pub struct GeometryWriter {
+    vertices: Vec<VertexData>,
+    indices: Vec<u32>,
+    draws: Vec<DrawCommand>,
+    draw_id: u32,
+    // the ring of buffers, with one set per frame in flight.
+    buffers: Vec<BufferSet>,
+    current: usize,
+    frames_in_flight: usize,
+    // the number of uploads and the ratio of `set_shrink_policy`.
+    shrink_policy: (usize, f32),
+    dirty: bool,
+}
+
+impl Default for GeometryWriter {
+    fn default() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            draws: Vec::new(),
+            draw_id: 0,
+            buffers: Vec::new(),
+            current: 0,
+            frames_in_flight: 1,
+            shrink_policy: (0, 0.5),
+            dirty: false,
+        }
+    }
+}
+
+impl GeometryWriter {
+    /// Create an empty [`GeometryWriter`]. No GPU resources are created
+    /// until it is first drawn.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of sets of GPU buffers the writer rotates between,
+    /// one for every upload of modified geometry. Defaults to `1`.
+    ///
+    /// With a single set, the buffers are mapped with
+    /// `D3D11_MAP_WRITE_DISCARD`, which lets the driver hand out fresh memory
+    /// while the GPU still reads the previous content. This is always safe,
+    /// and the best choice for most drivers. With `frames_in_flight` sets,
+    /// each upload writes to the next set with `D3D11_MAP_WRITE_NO_OVERWRITE`
+    /// instead, so the driver does not have to rename memory. This is only
+    /// correct if the GPU never lags more than `frames_in_flight - 1` uploads
+    /// behind the CPU, so it should be at least the maximum frame latency of
+    /// the swap chain (3 by default, see
+    /// `IDXGIDevice1::SetMaximumFrameLatency`), plus any other uploads per
+    /// frame, e.g. when rendering several viewports with the same writer.
+    ///
+    /// Each set takes as much video memory as the largest geometry it has
+    /// held, so the memory for geometry grows by `frames_in_flight` times.
+    /// Changing the setting releases all buffers, which are created again
+    /// by the next draw. `0` is treated as `1`. Buffers are always discarded
+    /// when drawing on a deferred context.
+    pub fn set_frames_in_flight(&mut self, frames_in_flight: usize) {
+        let frames_in_flight = frames_in_flight.max(1);
+        if frames_in_flight != self.frames_in_flight {
+            self.frames_in_flight = frames_in_flight;
+            self.buffers.clear();
+            self.current = 0;
+            self.dirty = true;
+        }
+    }
+
+    /// The number of sets of GPU buffers, see
+    /// [`GeometryWriter::set_frames_in_flight`].
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    /// Shrink a GPU buffer once `frames` uploads in a row have used less than
+    /// `ratio` of its capacity, e.g. `(60, 0.5)` to release the memory of a
+    /// single huge frame after a second of regular frames at 60 Hz. The
+    /// buffer is recreated with the capacity the current upload would have
+    /// grown it to. `ratio` is clamped to `0.0..=1.0`.
+    ///
+    /// Each vertex and index buffer counts its own uploads, so with several
+    /// sets (see [`GeometryWriter::set_frames_in_flight`]), a set is shrunk
+    /// after `frames` uploads to that set. A `frames` of `0` disables
+    /// shrinking, which is the default, so that buffers stay at their
+    /// high-water mark and are never reallocated for spiky geometry.
+    pub fn set_shrink_policy(&mut self, frames: usize, ratio: f32) {
+        self.shrink_policy = (frames, ratio.clamp(0., 1.));
+    }
+
+    /// The number of uploads and the ratio of capacity below which buffers
+    /// are shrunk, see [`GeometryWriter::set_shrink_policy`].
+    pub fn shrink_policy(&self) -> (usize, f32) {
+        self.shrink_policy
+    }
+
+    /// Make sure the CPU-side staging memory of the writer can hold at least
+    /// `vertices` vertices and `indices` indices without reallocating.
+    ///
+    /// The staging memory is kept across [`GeometryWriter::clear`], so it
+    /// only grows until it fits the largest geometry. Reserving ahead of time
+    /// avoids reallocations while it grows, e.g. in the first frames.
+    pub fn reserve(&mut self, vertices: usize, indices: usize) {
+        self.vertices
+            .reserve(vertices.saturating_sub(self.vertices.len()));
+        self.indices
+            .reserve(indices.saturating_sub(self.indices.len()));
+    }
+
+    /// Remove all appended geometry, keeping the allocated memory and GPU
+    /// buffers for reuse.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.draws.clear();
+        self.draw_id = 0;
+        self.dirty = true;
+    }
+
+    /// Set the id written to id buffers by meshes appended from now on, see
+    /// [`crate::Renderer::draw_appended_with_id_buffer`]. Defaults to `0`,
+    /// and is reset to `0` by [`GeometryWriter::clear`].
+    pub fn set_draw_id(&mut self, draw_id: u32) {
+        self.draw_id = draw_id;
+    }
+
+    /// Whether no geometry has been appended since the last call to
+    /// [`GeometryWriter::clear`].
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+
+    pub(crate) fn draws(&self) -> &[DrawCommand] {
+        &self.draws
+    }
+
+    /// Append the meshes of `primitives`. With `validate`, meshes which would
+    /// feed invalid data to the GPU are skipped with an error logged.
+    pub(crate) fn append<'a>(
+        &mut self,
+        primitives: impl IntoIterator<Item = &'a ClippedPrimitive>,
+        validate: bool,
+    ) {
+        for ClippedPrimitive {
+            clip_rect,
+            primitive,
+        } in primitives
+        {
+            let mesh = match primitive {
+                Primitive::Mesh(mesh) => mesh,
+                Primitive::Callback(..) => {
+                    log::warn!("paint callbacks are not yet supported.");
+                    continue;
+                },
+            };
+            if mesh.indices.is_empty() {
+                continue;
+            }
+            if mesh.indices.len() % 3 != 0 {
+                log::warn!(concat!(
+                    "egui wants to draw a incomplete triangle. ",
+                    "this request will be ignored."
+                ));
+                continue;
+            }
+            if validate && !Self::is_valid(clip_rect, mesh) {
+                continue;
+            }
+            let first_index = self.indices.len() as _;
+            let base_vertex = self.vertices.len() as _;
+            let mut bounds = Rect::NOTHING;
+            self.vertices.extend(mesh.vertices.iter().map(
+                |&Vertex { pos, uv, color }| {
+                    bounds.extend_with(pos);
+                    VertexData {
+                        pos,
+                        uv,
+                        color: [
+                            color[0] as f32 / 255.0,
+                            color[1] as f32 / 255.0,
+                            color[2] as f32 / 255.0,
+                            color[3] as f32 / 255.0,
+                        ],
+                    }
+                },
+            ));
+            self.draws.push(DrawCommand {
+                texture_id: mesh.texture_id,
+                clip_rect: *clip_rect,
+                bounds,
+                first_index,
+                index_count: mesh.indices.len() as _,
+                base_vertex,
+                draw_id: self.draw_id,
+            });
+            self.indices.extend_from_slice(&mesh.indices);
+            self.dirty = true;
+        }
+    }
+
+    /// Append raw geometry for `render_raw`, see its documentation for the
+    /// layout of `vertices` and `indices`.
+    pub(crate) fn append_raw(
+        &mut self,
+        vertices: &[u8],
+        indices: &[u8],
+        draws: &[RawDrawCommand],
+        validate: bool,
+    ) -> Result<()> {
+        if !vertices.len().is_multiple_of(RAW_VERTEX_SIZE)
+            || !indices.len().is_multiple_of(4)
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "the vertex and index buffers must consist of whole vertices and indices, got {} and {} bytes",
+                    vertices.len(),
+                    indices.len(),
+                ),
+            ));
+        }
+        let f32_at = |bytes: &[u8], at: usize| {
+            f32::from_ne_bytes(bytes[at..at + 4].try_into().unwrap())
+        };
+        let vertices = vertices
+            .chunks_exact(RAW_VERTEX_SIZE)
+            .map(|vertex| VertexData {
+                pos: Pos2::new(f32_at(vertex, 0), f32_at(vertex, 4)),
+                uv: Pos2::new(f32_at(vertex, 8), f32_at(vertex, 12)),
+                color: [16, 17, 18, 19].map(|at| vertex[at] as f32 / 255.0),
+            })
+            .collect::<Vec<_>>();
+        let indices = indices
+            .chunks_exact(4)
+            .map(|index| u32::from_ne_bytes(index.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        let first_index = self.indices.len() as u32;
+        let base_vertex = self.vertices.len() as _;
+        for draw in draws {
+            let [min_x, min_y, max_x, max_y] = draw.clip_rect;
+            let clip_rect = Rect::from_min_max(
+                Pos2::new(min_x, min_y),
+                Pos2::new(max_x, max_y),
+            );
+            let range = draw.index_offset as usize
+                ..draw.index_offset as usize + draw.index_count as usize;
+            let Some(draw_indices) = indices.get(range) else {
+                log::error!(
+                    "the indices {}..{} of a draw are out of range for {} indices. this request will be ignored.",
+                    draw.index_offset,
+                    draw.index_offset as usize + draw.index_count as usize,
+                    indices.len(),
+                );
+                continue;
+            };
+            if draw_indices.is_empty() {
+                continue;
+            }
+            if draw_indices.len() % 3 != 0 {
+                log::warn!(
+                    "a draw has an incomplete triangle. this request will be ignored."
+                );
+                continue;
+            }
+            let mut bounds = Rect::NOTHING;
+            let mut in_range = true;
+            for &index in draw_indices {
+                match vertices.get(index as usize) {
+                    Some(vertex) => bounds.extend_with(vertex.pos),
+                    None => in_range = false,
+                }
+            }
+            if !in_range {
+                log::error!(
+                    "a draw has indices out of range for {} vertices. this request will be ignored.",
+                    vertices.len(),
+                );
+                continue;
+            }
+            if validate && !Self::is_valid_clip_rect(&clip_rect) {
+                continue;
+            }
+            let Some(draw_first_index) =
+                first_index.checked_add(draw.index_offset)
+            else {
+                log::error!(
+                    "a draw starts at index {} after {first_index} indices appended before, which exceeds the range of 32-bit indices. this request will be ignored.",
+                    draw.index_offset,
+                );
+                continue;
+            };
+            self.draws.push(DrawCommand {
+                texture_id: match draw.texture_is_user {
+                    0 => TextureId::Managed(draw.texture_id),
+                    _ => TextureId::User(draw.texture_id),
+                },
+                clip_rect,
+                bounds,
+                first_index: draw_first_index,
+                index_count: draw.index_count,
+                base_vertex,
+                draw_id: self.draw_id,
+            });
+        }
+        self.vertices.extend(vertices);
+        self.indices.extend(indices);
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn is_valid(clip_rect: &Rect, mesh: &Mesh) -> bool {
+        let vertex_count = mesh.vertices.len();
+        if let Some(index) = mesh
+            .indices
+            .iter()
+            .find(|&&index| index as usize >= vertex_count)
+        {
+            log::error!(
+                "egui wants to draw a mesh whose index {index} is out of range for its {vertex_count} vertices. this request will be ignored."
+            );
+            return false;
+        }
+        Self::is_valid_clip_rect(clip_rect)
+    }
+
+    fn is_valid_clip_rect(clip_rect: &Rect) -> bool {
+        if !(clip_rect.min.is_finite() && clip_rect.max.is_finite())
+            || clip_rect.min.x > clip_rect.max.x
+            || clip_rect.min.y > clip_rect.max.y
+        {
+            log::error!(
+                "egui wants to draw a mesh with the degenerate clip rect {clip_rect:?}. this request will be ignored."
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Upload the geometry if it has been modified, and bind the buffers to
+    /// the input assembly stage.
+    pub(crate) fn bind(
+        &mut self,
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+    ) -> Result<()> {
+        if self.dirty {
+            if self.buffers.is_empty() {
+                self.buffers
+                    .resize_with(self.frames_in_flight, BufferSet::default);
+            } else {
+                self.current = (self.current + 1) % self.buffers.len();
+            }
+            // rotating buffers are only overwritten after all other sets
+            // have been written, see `set_frames_in_flight`. deferred
+            // contexts have to discard each buffer they map first anyway.
+            let no_overwrite = self.frames_in_flight > 1
+                && unsafe { ctx.GetType() } == D3D11_DEVICE_CONTEXT_IMMEDIATE;
+            let buffers = &mut self.buffers[self.current];
+            Self::shrink(
+                &mut buffers.vertex,
+                mem::size_of_val(self.vertices.as_slice()),
+                self.shrink_policy,
+            );
+            Self::shrink(
+                &mut buffers.index,
+                mem::size_of_val(self.indices.as_slice()),
+                self.shrink_policy,
+            );
+            Self::upload(
+                device,
+                ctx,
+                &mut buffers.vertex,
+                D3D11_BIND_VERTEX_BUFFER,
+                "vertices",
+                &self.vertices,
+                no_overwrite,
+            )?;
+            Self::upload(
+                device,
+                ctx,
+                &mut buffers.index,
+                D3D11_BIND_INDEX_BUFFER,
+                "indices",
+                &self.indices,
+                no_overwrite,
+            )?;
+            self.dirty = false;
+        }
+        let Some(BufferSet {
+            vertex: Some(vertex_buffer),
+            index: Some(index_buffer),
+        }) = self.buffers.get(self.current)
+        else {
+            return Ok(());
+        };
+        unsafe {
+            ctx.IASetVertexBuffers(
+                0,
+                1,
+                Some(&Some(vertex_buffer.buffer.clone())),
+                Some(&(mem::size_of::<VertexData>() as _)),
+                Some(&0),
+            );
+            ctx.IASetIndexBuffer(&index_buffer.buffer, DXGI_FORMAT_R32_UINT, 0);
+        }
+        Ok(())
+    }
+
+    /// Release `buffer` for `upload` to create a smaller one if the shrink
+    /// policy says so.
+    fn shrink(
+        buffer: &mut Option<DynamicBuffer>,
+        size: usize,
+        (frames, ratio): (usize, f32),
+    ) {
+        let Some(current) = buffer else {
+            return;
+        };
+        if frames == 0 || size == 0 {
+            return;
+        }
+        if (size as f32) < current.capacity as f32 * ratio {
+            current.underused += 1;
+        } else {
+            current.underused = 0;
+        }
+        if current.underused >= frames
+            && Self::buffer_capacity(size) < current.capacity
+        {
+            *buffer = None;
+        }
+    }
+
+    /// The capacity of a new buffer for `size` bytes of geometry.
+    fn buffer_capacity(size: usize) -> usize {
+        // grow geometrically, so that steadily growing geometry does not
+        // recreate the buffer every frame, but never beyond what every
+        // device supports unless the geometry itself needs it.
+        match size.next_power_of_two() {
+            capacity if capacity <= GUARANTEED_RESOURCE_SIZE => capacity,
+            _ => size.max(GUARANTEED_RESOURCE_SIZE),
+        }
+    }
+
+    fn upload<T>(
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+        buffer: &mut Option<DynamicBuffer>,
+        bind_flag: D3D11_BIND_FLAG,
+        kind: &str,
+        data: &[T],
+        no_overwrite: bool,
+    ) -> Result<()> {
+        let size = mem::size_of_val(data);
+        if size == 0 {
+            return Ok(());
+        }
+        if buffer.as_ref().is_none_or(|buffer| buffer.capacity < size) {
+            let capacity = Self::buffer_capacity(size);
+            if size > GUARANTEED_RESOURCE_SIZE {
+                log::warn!(
+                    "egui wants to draw {} {kind} ({size} bytes), which exceeds the buffer size every device supports.",
+                    data.len(),
+                );
+            }
+            let mut new_buffer = None;
+            unsafe {
+                device.CreateBuffer(
+                    &D3D11_BUFFER_DESC {
+                        ByteWidth: capacity as _,
+                        Usage: D3D11_USAGE_DYNAMIC,
+                        BindFlags: bind_flag.0 as _,
+                        CPUAccessFlags: D3D11_CPU_ACCESS_WRITE.0 as _,
+                        ..D3D11_BUFFER_DESC::default()
+                    },
+                    None,
+                    Some(&mut new_buffer),
+                )
+            }
+            .map_err(|err| {
+                Error::new(
+                    err.code(),
+                    format!(
+                        "failed to create a buffer of {capacity} bytes for {} {kind}: {}",
+                        data.len(),
+                        err.message(),
+                    ),
+                )
+            })?;
+            *buffer = Some(DynamicBuffer {
+                buffer: new_buffer.unwrap(),
+                capacity,
+                written: false,
+                underused: 0,
+            });
+        }
+        let buffer = buffer.as_mut().unwrap();
+        // new buffers are discarded on their first map.
+        let map_type = if no_overwrite && buffer.written {
+            D3D11_MAP_WRITE_NO_OVERWRITE
+        } else {
+            D3D11_MAP_WRITE_DISCARD
+        };
+        unsafe {
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            ctx.Map(&buffer.buffer, 0, map_type, 0, Some(&mut mapped))?;
+            (mapped.pData as *mut T)
+                .copy_from_nonoverlapping(data.as_ptr(), data.len());
+            ctx.Unmap(&buffer.buffer, 0);
+        }
+        buffer.written = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex_bytes(positions: &[[f32; 2]]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for [x, y] in positions {
+            for value in [x, y, &0., &0.] {
+                bytes.extend_from_slice(&value.to_ne_bytes());
+            }
+            bytes.extend_from_slice(&[255; 4]);
+        }
+        bytes
+    }
+
+    fn index_bytes(indices: &[u32]) -> Vec<u8> {
+        indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes())
+            .collect()
+    }
+
+    fn raw_draw(index_offset: u32, index_count: u32) -> RawDrawCommand {
+        RawDrawCommand {
+            clip_rect: [0., 0., 100., 100.],
+            index_offset,
+            index_count,
+            ..RawDrawCommand::default()
+        }
+    }
+
+    #[test]
+    fn append_raw_rejects_partial_vertices_and_indices() {
+        let mut writer = GeometryWriter::new();
+        let vertices = vertex_bytes(&[[0., 0.]; 3]);
+        let indices = index_bytes(&[0, 1, 2]);
+        let draws = [raw_draw(0, 3)];
+        assert!(
+            writer
+                .append_raw(&vertices[1..], &indices, &draws, true)
+                .is_err()
+        );
+        assert!(
+            writer
+                .append_raw(&vertices, &indices[1..], &draws, true)
+                .is_err()
+        );
+        assert!(writer.is_empty());
+    }
+
+    #[test]
+    fn append_raw_skips_invalid_draws() {
+        let mut writer = GeometryWriter::new();
+        let vertices = vertex_bytes(&[[1., 2.], [3., 4.], [5., 0.]]);
+        let indices = index_bytes(&[0, 1, 2, 0, 1, 3]);
+        let mut degenerate = raw_draw(0, 3);
+        degenerate.clip_rect = [1., 0., 0., 1.];
+        let draws = [
+            raw_draw(0, 3),
+            // out of range of the indices.
+            raw_draw(3, 6),
+            // an incomplete triangle.
+            raw_draw(0, 2),
+            // the index 3 is out of range of the vertices.
+            raw_draw(3, 3),
+            degenerate,
+            raw_draw(0, 0),
+        ];
+        writer
+            .append_raw(&vertices, &indices, &draws, true)
+            .unwrap();
+        let [draw] = writer.draws() else {
+            panic!("only the first draw is valid");
+        };
+        assert_eq!((draw.first_index, draw.index_count), (0, 3));
+        assert_eq!(
+            draw.bounds,
+            Rect::from_min_max(Pos2::new(1., 0.), Pos2::new(5., 4.))
+        );
+
+        // later draws are offset by the geometry appended before them.
+        writer
+            .append_raw(&vertices, &indices, &[raw_draw(0, 3)], true)
+            .unwrap();
+        let draw = &writer.draws()[1];
+        assert_eq!((draw.first_index, draw.base_vertex), (6, 3));
+    }
+
+    #[test]
+    fn buffers_grow_geometrically_up_to_the_guaranteed_size() {
+        assert_eq!(GeometryWriter::buffer_capacity(1), 1);
+        assert_eq!(GeometryWriter::buffer_capacity(1000), 1024);
+        assert_eq!(GeometryWriter::buffer_capacity(1024), 1024);
+        let size = GUARANTEED_RESOURCE_SIZE / 2 + 1;
+        assert_eq!(
+            GeometryWriter::buffer_capacity(size),
+            GUARANTEED_RESOURCE_SIZE
+        );
+        let size = GUARANTEED_RESOURCE_SIZE + 1;
+        assert_eq!(GeometryWriter::buffer_capacity(size), size);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn buffers_shrink_after_frames_below_the_ratio() {
+        let (device, ctx, _) = crate::Renderer::new_warp().unwrap();
+        let mut buffer = None;
+        GeometryWriter::upload(
+            &device,
+            &ctx,
+            &mut buffer,
+            D3D11_BIND_VERTEX_BUFFER,
+            "vertices",
+            &[0u8; 4096],
+            false,
+        )
+        .unwrap();
+        assert_eq!(buffer.as_ref().unwrap().capacity, 4096);
+
+        let policy = (3, 0.5);
+        // disabled by a `frames` of 0.
+        for _ in 0..10 {
+            GeometryWriter::shrink(&mut buffer, 16, (0, 0.5));
+        }
+        assert!(buffer.is_some());
+        // a single upload above the ratio restarts the count.
+        GeometryWriter::shrink(&mut buffer, 16, policy);
+        GeometryWriter::shrink(&mut buffer, 16, policy);
+        GeometryWriter::shrink(&mut buffer, 4000, policy);
+        GeometryWriter::shrink(&mut buffer, 16, policy);
+        GeometryWriter::shrink(&mut buffer, 16, policy);
+        assert!(buffer.is_some());
+        GeometryWriter::shrink(&mut buffer, 16, policy);
+        assert!(buffer.is_none());
+    }
+}
@@ -23,40 +23,464 @@
 //! crate. You can also take a look at the [`egui-demo`](https://github.com/Nekomaru-PKU/egui-directx11/blob/main/examples/egui-demo.rs) example, which demonstrates all you need to do to set up a minimal application
 //! with Direct3D11 and `egui`. This example uses `winit` for window management
 //! and event handling, while native Win32 APIs should also work well.
+//!
+//! ## Shader Compilation
+//!
+//! By default, shaders are compiled from HLSL sources with `D3DCompile` in
+//! [`Renderer::new`]. This requires no tooling at build time and only depends
+//! on `d3dcompiler_47.dll` which ships with Windows, but it introduces a
+//! noticeable hitch when creating a [`Renderer`].
+//!
+//! If startup latency matters to you (e.g. for overlays injected into an
+//! already running game), enable the `precompiled` feature. The build script
+//! then compiles the shaders with `fxc` from the Windows SDK and embeds the
+//! bytecode into the binary. `fxc` must be in `PATH`, or you may set the `FXC`
+//! environment variable to its full path.
 
+mod blit;
+#[cfg(feature = "dds")]
+mod dds;
+#[cfg(feature = "debug-layer")]
+mod debug_layer;
+mod geometry;
+#[cfg(feature = "win32-platform")]
+mod platform;
+mod render_target;
+mod sampler;
+#[cfg(feature = "setup")]
+mod setup;
+mod shader;
+mod state;
 mod texture;
-use texture::TexturePool;
+mod timing;
+use blit::Blitter;
+use geometry::DrawCommand;
+use render_target::RenderTargetCache;
+use sampler::SamplerCache;
+use state::StateBackup;
+use texture::{TextureBinding, TexturePool};
+use timing::{GpuTimer, get_query_data};
+
+pub use geometry::{GeometryWriter, RAW_VERTEX_SIZE, RawDrawCommand};
+#[cfg(feature = "win32-platform")]
+pub use platform::{handle_platform_output_win32, set_cursor_win32};
+pub use sampler::{AddressMode, SamplerOptions};
+#[cfg(feature = "setup")]
+pub use setup::SwapChainConfig;
+pub use texture::{
+    AlphaMode, BlendMode, NativeTextureOptions, TextureColorSpace,
+    TextureObserver, VideoFormat,
+};
 
-use std::mem;
+use std::{
+    collections::HashSet,
+    mem,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Instant,
+};
 
 const fn zeroed<T>() -> T {
     unsafe { mem::zeroed() }
 }
 
 use egui::{
-    ClippedPrimitive, Pos2, TextureId,
-    epaint::{ClippedShape, Primitive, Vertex, textures::TexturesDelta},
+    ClippedPrimitive, TextureId, Vec2,
+    epaint::{ClippedShape, textures::TexturesDelta},
 };
 
-use windows::Win32::Foundation::RECT;
-use windows::Win32::Graphics::{Direct3D::*, Direct3D11::*, Dxgi::Common::*};
+use windows::Win32::Foundation::{E_INVALIDARG, RECT};
+use windows::Win32::Graphics::{
+    Direct3D::*,
+    Direct3D11::*,
+    Dxgi::{
+        Common::*, DXGI_FEATURE_PRESENT_ALLOW_TEARING, DXGI_PRESENT,
+        DXGI_PRESENT_ALLOW_TEARING, DXGI_SWAP_CHAIN_FLAG,
+        DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING, IDXGIDevice, IDXGIFactory5,
+        IDXGIOutput6, IDXGISwapChain,
+    },
+};
 use windows::core::BOOL;
-use windows::core::{Interface, Result};
+use windows::core::{Error, Interface, Result};
 
 /// The core of this crate. You can set up a renderer via [`Renderer::new`]
 /// and render the output from `egui` with [`Renderer::render`].
+///
+/// ## Cloning
+///
+/// Cloning a [`Renderer`] is cheap, as Direct3D11 objects are reference
+/// counted. The clone shares the device, the shaders and pipeline states,
+/// which are immutable, and the pool of textures (see [`SharedTexturePool`])
+/// with the original, so textures registered with either are visible to both.
+/// The clone starts with the current settings of the original, which are not
+/// shared afterwards. It has its own geometry buffers, so nothing is drawn by
+/// [`Renderer::render_cached`] before its first [`Renderer::render`], its own
+/// cache of render target views, and GPU timing has to be enabled for it
+/// separately with [`Renderer::enable_gpu_timing`].
+///
+/// ## Transparent windows
+///
+/// The renderer always outputs premultiplied colors, and blends alpha with
+/// the "over" operator, i.e. the alpha of the render target becomes
+/// `src + dst * (1 - src)`. A frame cleared to transparent black therefore
+/// holds correct premultiplied alpha after rendering, as expected by
+/// swap chains created with `DXGI_ALPHA_MODE_PREMULTIPLIED` for
+/// DirectComposition, and by `UpdateLayeredWindow` with `AC_SRC_ALPHA`. No
+/// setting is needed for this, see `examples/transparent_overlay.rs`.
 pub struct Renderer {
     device: ID3D11Device,
     input_layout: ID3D11InputLayout,
     vertex_shader: ID3D11VertexShader,
-    pixel_shader: ID3D11PixelShader,
-    rasterizer_state: ID3D11RasterizerState,
-    sampler_state: ID3D11SamplerState,
-    blend_state: ID3D11BlendState,
+    pixel_shaders: PixelShaders,
+    // the shader resource slot of textures, see `set_texture_slot`.
+    texture_slot: u32,
+    rasterizer_desc: D3D11_RASTERIZER_DESC,
+    rasterizer_state: Option<ID3D11RasterizerState>,
+    sampler_cache: SamplerCache,
+    blend_states: BlendStates,
+    depth_func: DepthFunc,
+    depth_states: DepthStates,
+    constant_buffer: ID3D11Buffer,
+    draw_constant_buffer: ID3D11Buffer,
+    // uploaded to `callback_constant_buffer` by each frame, see
+    // `set_callback_constants`.
+    callback_constants: Vec<u8>,
+    callback_constant_buffer: Option<ID3D11Buffer>,
+
+    blitter: Option<Blitter>,
+    geometry: GeometryWriter,
+    geometry_pixels_per_point: f32,
+    gpu_timer: Option<GpuTimer>,
+    cpu_timing: bool,
+    last_cpu_time_us: Option<u64>,
+
+    texture_pool: Arc<Mutex<TexturePool>>,
+    render_target_cache: RenderTargetCache,
+    last_freed_textures: Vec<TextureId>,
+    missing_texture_policy: MissingTexturePolicy,
+    // created when first needed by `MissingTexturePolicy::Magenta`.
+    missing_texture: Option<ID3D11ShaderResourceView>,
+    // ids of missing textures already warned about.
+    warned_missing_textures: HashSet<TextureId>,
+
+    preserve_state: bool,
+    manage_states: bool,
+    flip_y: bool,
+    composite_mode: CompositeMode,
+    validate: bool,
+    projection_override: Option<[[f32; 4]; 4]>,
+    // set by `render_to_texture_fit` for the duration of the frame.
+    output_transform: Option<OutputTransform>,
+    layer_opacity: f32,
+    color_matrix: Option<[[f32; 4]; 4]>,
+    vertex_color_space: VertexColorSpace,
+    output_color_space: DXGI_COLOR_SPACE_TYPE,
+    mismatched_frame_size: Option<(u32, u32)>,
+    screen_size: [f32; 2],
+}
+
+impl Clone for Renderer {
+    fn clone(&self) -> Self {
+        Self {
+            device: self.device.clone(),
+            input_layout: self.input_layout.clone(),
+            vertex_shader: self.vertex_shader.clone(),
+            pixel_shaders: self.pixel_shaders.clone(),
+            texture_slot: self.texture_slot,
+            rasterizer_desc: self.rasterizer_desc,
+            rasterizer_state: self.rasterizer_state.clone(),
+            sampler_cache: self.sampler_cache.clone(),
+            blend_states: self.blend_states.clone(),
+            depth_func: self.depth_func,
+            depth_states: self.depth_states.clone(),
+            constant_buffer: self.constant_buffer.clone(),
+            draw_constant_buffer: self.draw_constant_buffer.clone(),
+            callback_constants: self.callback_constants.clone(),
+            callback_constant_buffer: self.callback_constant_buffer.clone(),
+            blitter: self.blitter.clone(),
+            geometry: {
+                let mut geometry = GeometryWriter::new();
+                geometry.set_frames_in_flight(self.geometry.frames_in_flight());
+                let (frames, ratio) = self.geometry.shrink_policy();
+                geometry.set_shrink_policy(frames, ratio);
+                geometry
+            },
+            geometry_pixels_per_point: 1.,
+            gpu_timer: None,
+            cpu_timing: self.cpu_timing,
+            last_cpu_time_us: None,
+            texture_pool: self.texture_pool.clone(),
+            render_target_cache: RenderTargetCache::new(),
+            last_freed_textures: Vec::new(),
+            missing_texture_policy: self.missing_texture_policy,
+            missing_texture: self.missing_texture.clone(),
+            warned_missing_textures: HashSet::new(),
+            preserve_state: self.preserve_state,
+            manage_states: self.manage_states,
+            flip_y: self.flip_y,
+            composite_mode: self.composite_mode,
+            validate: self.validate,
+            projection_override: self.projection_override,
+            output_transform: None,
+            layer_opacity: self.layer_opacity,
+            color_matrix: self.color_matrix,
+            vertex_color_space: self.vertex_color_space,
+            output_color_space: self.output_color_space,
+            mismatched_frame_size: None,
+            screen_size: self.screen_size,
+        }
+    }
+}
+
+/// Which triangles are culled by the renderer. See
+/// [`Renderer::set_cull_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CullMode {
+    /// Draw all triangles.
+    #[default]
+    None,
+    /// Do not draw triangles facing the front, i.e. clockwise ones.
+    Front,
+    /// Do not draw triangles facing the back, i.e. counter-clockwise ones.
+    Back,
+}
+
+/// How [`Renderer::render_to_texture_fit`] fits the logical canvas of `egui`
+/// into a texture of a different aspect ratio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Scale the canvas independently along both axes to cover the whole
+    /// texture, distorting it if the aspect ratios differ.
+    #[default]
+    Stretch,
+    /// Scale the canvas uniformly to the largest size that fits, and center
+    /// it in the texture. The bars left at two sides are not drawn to.
+    Letterbox,
+}
+
+/// Presets for [`Renderer::set_color_matrix`], in row-major order for linear
+/// colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorMatrix(pub [[f32; 4]; 4]);
+
+impl ColorMatrix {
+    /// Leaves colors as they are.
+    pub const IDENTITY: Self = Self([
+        [1., 0., 0., 0.],
+        [0., 1., 0., 0.],
+        [0., 0., 1., 0.],
+        [0., 0., 0., 1.],
+    ]);
+
+    /// Replace colors by their luminance with rec. 709 primaries.
+    pub const fn grayscale() -> Self {
+        const LUMA: [f32; 4] = [0.2126, 0.7152, 0.0722, 0.];
+        Self([LUMA, LUMA, LUMA, [0., 0., 0., 1.]])
+    }
+
+    /// Simulate deuteranopia, i.e. the lack of green cones, with the model of
+    /// Machado et al. (2009) at full severity.
+    pub const fn deuteranopia() -> Self {
+        Self([
+            [0.367322, 0.860646, -0.227968, 0.],
+            [0.280085, 0.672501, 0.047413, 0.],
+            [-0.011820, 0.042940, 0.968881, 0.],
+            [0., 0., 0., 1.],
+        ])
+    }
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl From<ColorMatrix> for [[f32; 4]; 4] {
+    fn from(matrix: ColorMatrix) -> Self {
+        matrix.0
+    }
+}
+
+/// Maps the canvas of `egui` in points to a rectangle of the render target in
+/// pixels, with a separate scale for each axis.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct OutputTransform {
+    // the size of the canvas in points.
+    size: Vec2,
+    scale: Vec2,
+    offset: Vec2,
+}
+
+impl OutputTransform {
+    /// The whole render target at `pixels_per_point`, as `egui` expects.
+    fn uniform(pixels_per_point: f32, frame_size: (u32, u32)) -> Self {
+        let frame_size = Vec2::new(frame_size.0 as _, frame_size.1 as _);
+        Self {
+            size: frame_size / pixels_per_point,
+            scale: Vec2::splat(pixels_per_point),
+            offset: Vec2::ZERO,
+        }
+    }
+
+    fn fit(size: Vec2, frame_size: (u32, u32), fit: FitMode) -> Self {
+        let frame_size = Vec2::new(frame_size.0 as _, frame_size.1 as _);
+        let scale = frame_size / size;
+        match fit {
+            FitMode::Stretch => Self {
+                size,
+                scale,
+                offset: Vec2::ZERO,
+            },
+            FitMode::Letterbox => {
+                let scale = Vec2::splat(scale.min_elem());
+                Self {
+                    size,
+                    scale,
+                    offset: (frame_size - size * scale) * 0.5,
+                }
+            },
+        }
+    }
+
+    fn viewport(&self, frame_height: u32, flip_y: bool) -> D3D11_VIEWPORT {
+        let size = self.size * self.scale;
+        let top = if flip_y {
+            frame_height as f32 - self.offset.y - size.y
+        } else {
+            self.offset.y
+        };
+        D3D11_VIEWPORT {
+            TopLeftX: self.offset.x,
+            TopLeftY: top,
+            Width: size.x,
+            Height: size.y,
+            MinDepth: 0.,
+            MaxDepth: 1.,
+        }
+    }
+}
+
+/// How triangles are rasterized by the renderer. See
+/// [`Renderer::set_fill_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillMode {
+    /// Fill triangles, as `egui` expects.
+    #[default]
+    Solid,
+    /// Only draw the edges of triangles, for debugging tessellation.
+    Wireframe,
+}
+
+/// How the depth of `egui` is compared against a depth buffer. See
+/// [`Renderer::set_depth_func`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthFunc {
+    /// Draw where the depth is less than or equal to the depth buffer, for
+    /// the conventional depth range where `0.0` is near.
+    LessEqual,
+    /// Draw where the depth is greater than or equal to the depth buffer, for
+    /// reversed-Z depth, where `1.0` is near.
+    GreaterEqual,
+    /// Always draw, i.e. never occlude `egui`.
+    #[default]
+    Always,
+}
+
+/// How the output of the renderer is combined with the content of the render
+/// target. See [`Renderer::set_composite_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CompositeMode {
+    /// Blend colors over the render target as `egui` expects, and as the
+    /// blend modes of native textures specify.
+    #[default]
+    Blend,
+    /// Write colors and alpha as output by the pixel shader, ignoring the
+    /// content of the render target.
+    Opaque,
+}
+
+/// A capability of the device, which can be queried with
+/// [`Renderer::check_support`] before relying on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// Render targets of `format` with `samples` samples per pixel, e.g. for
+    /// rendering `egui` into a multisampled render target.
+    Msaa {
+        #[allow(missing_docs)]
+        format: DXGI_FORMAT,
+        #[allow(missing_docs)]
+        samples: u32,
+    },
+    /// Generating the mip levels of textures of the format with
+    /// `ID3D11DeviceContext::GenerateMips`.
+    GenerateMips(DXGI_FORMAT),
+    /// HDR10 output, i.e. blendable `DXGI_FORMAT_R10G10B10A2_UNORM` render
+    /// targets and a display of the adapter currently in HDR10 mode. See
+    /// [`Renderer::set_output_color_space`].
+    Hdr10Output,
+    /// scRGB output, i.e. blendable `DXGI_FORMAT_R16G16B16A16_FLOAT` render
+    /// targets. See [`Renderer::set_output_color_space`].
+    ScRgbOutput,
+    /// Presenting with tearing, see [`Renderer::present`].
+    Tearing,
+}
+
+/// What the renderer does when `egui` draws with a texture it does not know,
+/// e.g. a native texture that has been removed while still in use. See
+/// [`Renderer::set_missing_texture_policy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingTexturePolicy {
+    /// Skip the draw silently.
+    Skip,
+    /// Draw with a built-in magenta texture, which flags the missing texture
+    /// visibly on screen.
+    Magenta,
+    /// Skip the draw, and log a warning the first time each unknown texture
+    /// is drawn.
+    #[default]
+    Warn,
+}
 
-    texture_pool: TexturePool,
+/// The color space of the vertex colors drawn by the renderer. See
+/// [`Renderer::set_vertex_color_space`].
+// keep in sync with `VERTEX_*` in `shaders/egui.hlsl`.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VertexColorSpace {
+    /// Vertex colors are in gamma space, as produced by `egui`.
+    #[default]
+    Gamma = 0,
+    /// Vertex colors are in linear space, e.g. converted by an engine working
+    /// in linear space only.
+    Linear = 1,
 }
 
+/// A pool of textures that can be shared between multiple [`Renderer`]s
+/// created on the same device.
+///
+/// By default each [`Renderer`] maintains its own pool of textures, so if you
+/// render with multiple [`Renderer`]s (e.g. a main view and a thumbnail view),
+/// textures like the font atlas are uploaded once per [`Renderer`]. Create the
+/// other [`Renderer`]s with [`Renderer::new_with_shared_texture_pool`] to
+/// avoid this.
+///
+/// As textures managed by `egui` are identified per [`egui::Context`], this is
+/// only meaningful when the [`Renderer`]s render outputs of the same context.
+/// Since the texture pool is shared, [`egui::TexturesDelta`] of an output
+/// should be applied by only one of the [`Renderer`]s. Pass an empty delta
+/// (e.g. with `std::mem::take`) to the others.
+///
+/// Sampler states are not part of the pool. Each [`Renderer`] creates them
+/// lazily in its own cache, including the one of the default options set with
+/// [`Renderer::set_default_sampler_options`] by another [`Renderer`].
+///
+/// The pool is protected by a mutex, which is locked while a [`Renderer`]
+/// updates textures or draws. [`Renderer`]s sharing a pool may live on
+/// different threads, but their rendering is serialized by the lock. The usual
+/// threading rules of Direct3D11 device contexts still apply.
+#[derive(Clone)]
+pub struct SharedTexturePool(Arc<Mutex<TexturePool>>);
+
 /// Part of [`egui::FullOutput`] that is consumed by [`Renderer::render`].
 ///
 /// Call to [`egui::Context::run`] or [`egui::Context::end_frame`] yields a
@@ -73,44 +497,486 @@ pub struct RendererOutput {
     pub pixels_per_point: f32,
 }
 
+/// Part of [`egui::FullOutput`] that describes what `egui` wants to happen to
+/// each viewport, keyed by [`egui::ViewportId`].
+///
+/// Each [`egui::ViewportOutput`] carries the [`egui::ViewportCommand`]s for
+/// its viewport (e.g. changing the title, resizing or closing the window) and
+/// the [`egui::ViewportBuilder`] describing the window. Single-window
+/// applications only need to act on the commands sent to
+/// [`egui::ViewportId::ROOT`]. Multi-viewport applications should also create
+/// a window for every viewport that appears in this map and destroy windows of
+/// viewports that no longer appear.
+///
+/// If you are using `egui_winit`, the commands can be applied with
+/// `egui_winit::process_viewport_commands`.
+pub type ViewportOutputs = egui::ViewportIdMap<egui::ViewportOutput>;
+
+/// A [`egui::FullOutput`] split into the part consumed by the renderer and
+/// the parts consumed by the platform integration. Returned by
+/// [`split_output`].
+///
+/// For migrating from the tuple previously returned by [`split_output`], a
+/// [`SplitOutput`] can be converted into a tuple with [`Into::into`]:
+/// ```ignore
+/// let (renderer_output, platform_output, viewport_output) =
+///     egui_directx11::split_output(full_output).into();
+/// ```
+pub struct SplitOutput {
+    /// The part consumed by [`Renderer::render`], which takes only this field
+    /// so that the other fields are not dropped by accident.
+    pub renderer_output: RendererOutput,
+    /// The part consumed by the platform integration, e.g. by
+    /// `egui_winit::State::handle_platform_output`. It carries clipboard,
+    /// cursor and IME requests among others. Without `egui-winit`, the
+    /// `win32-platform` feature provides `handle_platform_output_win32` for
+    /// the cursor, the clipboard and URLs.
+    pub platform_output: egui::PlatformOutput,
+    /// Requests for each viewport. See [`ViewportOutputs`] for details.
+    pub viewport_output: ViewportOutputs,
+}
+
+impl From<egui::FullOutput> for SplitOutput {
+    fn from(full_output: egui::FullOutput) -> Self {
+        Self {
+            renderer_output: RendererOutput {
+                textures_delta: full_output.textures_delta,
+                shapes: full_output.shapes,
+                pixels_per_point: full_output.pixels_per_point,
+            },
+            platform_output: full_output.platform_output,
+            viewport_output: full_output.viewport_output,
+        }
+    }
+}
+
+impl From<SplitOutput>
+    for (RendererOutput, egui::PlatformOutput, ViewportOutputs)
+{
+    fn from(split_output: SplitOutput) -> Self {
+        (
+            split_output.renderer_output,
+            split_output.platform_output,
+            split_output.viewport_output,
+        )
+    }
+}
+
 /// Convenience method to split a [`egui::FullOutput`] into the
 /// [`RendererOutput`] part and other parts for platform integration.
 ///
-/// The returned tuple should be destructured as:
+/// The returned [`SplitOutput`] can be destructured as:
 /// ```ignore
-/// let (renderer_output, platform_output, viewport_output) =
-///     egui_directx11::split_output(full_output);
+/// let egui_directx11::SplitOutput {
+///     renderer_output,
+///     platform_output,
+///     viewport_output,
+/// } = egui_directx11::split_output(full_output);
 /// ```
-pub fn split_output(
-    full_output: egui::FullOutput,
-) -> (
-    RendererOutput,
-    egui::PlatformOutput,
-    egui::ViewportIdMap<egui::ViewportOutput>,
-) {
-    (
-        RendererOutput {
-            textures_delta: full_output.textures_delta,
-            shapes: full_output.shapes,
-            pixels_per_point: full_output.pixels_per_point,
-        },
-        full_output.platform_output,
-        full_output.viewport_output,
-    )
+pub fn split_output(full_output: egui::FullOutput) -> SplitOutput {
+    full_output.into()
 }
 
+/// The size in bytes of a vertex in the vertex buffers of the [`Renderer`],
+/// as described by [`Renderer::INPUT_ELEMENTS_DESC`].
+///
+/// Note that this differs from [`egui::epaint::Vertex`]: each vertex consists
+/// of the position in points as two `f32` at offset 0 (`POSITION`), the
+/// texture coordinates as two `f32` at offset 8 (`TEXCOORD`), and the
+/// premultiplied color in gamma space as four `f32` in `0.0..=1.0` at offset
+/// 16 (`COLOR`), without any padding.
+pub const VERTEX_STRIDE: usize = mem::size_of::<geometry::VertexData>();
+
+// keep in sync with `OUTPUT_*` in `shaders/egui.hlsl`.
+#[repr(u32)]
+#[derive(Clone, Copy)]
+enum OutputEncoding {
+    Gamma = 0,
+    Linear = 1,
+    Pq = 2,
+}
+
+// keep in sync with `egui_constants` in `shaders/egui.hlsl`.
 #[repr(C)]
-struct VertexData {
-    pos: Pos2,
-    uv: Pos2,
-    color: [f32; 4],
+struct Constants {
+    projection: [[f32; 4]; 4],
+    output_encoding: OutputEncoding,
+    opacity: f32,
+    vertex_color_space: VertexColorSpace,
+    // whether `color_matrix` is applied, as a `bool` of HLSL.
+    use_color_matrix: u32,
+    color_matrix: [[f32; 4]; 4],
+}
+
+// keep in sync with `egui_draw_constants` in `shaders/egui.hlsl`. updated
+// between draws whenever it changes.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq)]
+struct DrawConstants {
+    emissive: f32,
+    draw_id: u32,
+    texture_color_space: TextureColorSpace,
+    _padding: u32,
+}
+
+impl Default for DrawConstants {
+    fn default() -> Self {
+        Self {
+            emissive: 1.,
+            draw_id: 0,
+            texture_color_space: TextureColorSpace::Srgb,
+            _padding: 0,
+        }
+    }
+}
+
+/// An id buffer to draw to along with a render target, and the function
+/// assigning ids to primitives.
+type IdBuffer<'a> = (
+    &'a ID3D11RenderTargetView,
+    &'a mut dyn FnMut(&ClippedPrimitive) -> u32,
+);
+
+/// A depth stencil view to test against, and the reference value the stencil
+/// must equal for `egui` to be drawn if the stencil is tested.
+type DepthStencil<'a> = (&'a ID3D11DepthStencilView, Option<u8>);
+
+/// How a frame is drawn by the variants of [`Renderer::render`].
+#[derive(Default)]
+struct FrameOptions<'a> {
+    id_buffer: Option<IdBuffer<'a>>,
+    depth_stencil: Option<DepthStencil<'a>>,
+    // decides which primitives are drawn.
+    filter: Option<&'a mut dyn FnMut(&ClippedPrimitive) -> bool>,
+}
+
+#[derive(Clone)]
+struct PixelShaders {
+    egui: ID3D11PixelShader,
+    straight: ID3D11PixelShader,
+    straight_premultiplied: ID3D11PixelShader,
+    mask: ID3D11PixelShader,
+    nv12: ID3D11PixelShader,
+    id: ID3D11PixelShader,
+}
+
+impl PixelShaders {
+    fn new(device: &ID3D11Device, texture_slot: u32) -> Result<Self> {
+        let create = |shader| Self::create(device, shader, texture_slot);
+        Ok(Self {
+            egui: create(&shader::PS_EGUI)?,
+            straight: create(&shader::PS_EGUI_STRAIGHT)?,
+            straight_premultiplied: create(
+                &shader::PS_EGUI_STRAIGHT_PREMULTIPLIED,
+            )?,
+            mask: create(&shader::PS_EGUI_MASK)?,
+            nv12: create(&shader::PS_EGUI_NV12)?,
+            id: create(&shader::PS_EGUI_ID)?,
+        })
+    }
+
+    fn create(
+        device: &ID3D11Device,
+        shader: &shader::Shader,
+        texture_slot: u32,
+    ) -> Result<ID3D11PixelShader> {
+        let blob = shader.bytecode_for_slot(texture_slot)?;
+        let mut pixel_shader = None;
+        unsafe {
+            device.CreatePixelShader(&blob, None, Some(&mut pixel_shader))
+        }?;
+        Ok(pixel_shader.unwrap())
+    }
+
+    /// All shaders, with the one of id passes last.
+    fn all(&self) -> [&ID3D11PixelShader; 6] {
+        [
+            &self.egui,
+            &self.straight,
+            &self.straight_premultiplied,
+            &self.mask,
+            &self.nv12,
+            &self.id,
+        ]
+    }
+
+    fn get(&self, binding: &TextureBinding) -> &ID3D11PixelShader {
+        match binding.video.as_ref().map(|video| video.format) {
+            None if binding.mask => &self.mask,
+            None => match (binding.alpha_mode, binding.blend_mode) {
+                (AlphaMode::Premultiplied, _) => &self.egui,
+                // its blend state can not multiply colors by alpha.
+                (AlphaMode::Straight, BlendMode::Multiply) => {
+                    &self.straight_premultiplied
+                },
+                (AlphaMode::Straight, _) => &self.straight,
+            },
+            Some(VideoFormat::Nv12) => &self.nv12,
+        }
+    }
+}
+
+/// The depth stencil states for the depth function of the renderer, which
+/// never write depth or stencil.
+#[derive(Clone)]
+struct DepthStates {
+    // depth test only, also bound when no depth buffer is.
+    depth: ID3D11DepthStencilState,
+    // depth test and stencil test, for `render_with_stencil_mask`.
+    stencil: ID3D11DepthStencilState,
+}
+
+impl DepthStates {
+    fn new(device: &ID3D11Device, depth_func: DepthFunc) -> Result<Self> {
+        let depth_func = match depth_func {
+            DepthFunc::LessEqual => D3D11_COMPARISON_LESS_EQUAL,
+            DepthFunc::GreaterEqual => D3D11_COMPARISON_GREATER_EQUAL,
+            DepthFunc::Always => D3D11_COMPARISON_ALWAYS,
+        };
+        // pass where the stencil equals the reference, and keep it as is.
+        let face = D3D11_DEPTH_STENCILOP_DESC {
+            StencilFailOp: D3D11_STENCIL_OP_KEEP,
+            StencilDepthFailOp: D3D11_STENCIL_OP_KEEP,
+            StencilPassOp: D3D11_STENCIL_OP_KEEP,
+            StencilFunc: D3D11_COMPARISON_EQUAL,
+        };
+        let depth = D3D11_DEPTH_STENCIL_DESC {
+            DepthEnable: (depth_func != D3D11_COMPARISON_ALWAYS).into(),
+            DepthWriteMask: D3D11_DEPTH_WRITE_MASK_ZERO,
+            DepthFunc: depth_func,
+            StencilEnable: BOOL(0),
+            StencilReadMask: D3D11_DEFAULT_STENCIL_READ_MASK as _,
+            StencilWriteMask: 0,
+            FrontFace: face,
+            BackFace: face,
+        };
+        let stencil = D3D11_DEPTH_STENCIL_DESC {
+            StencilEnable: BOOL(1),
+            ..depth
+        };
+        Ok(Self {
+            depth: Self::create(device, &depth)?,
+            stencil: Self::create(device, &stencil)?,
+        })
+    }
+
+    fn create(
+        device: &ID3D11Device,
+        desc: &D3D11_DEPTH_STENCIL_DESC,
+    ) -> Result<ID3D11DepthStencilState> {
+        let mut depth_stencil_state = None;
+        unsafe {
+            device.CreateDepthStencilState(desc, Some(&mut depth_stencil_state))
+        }?;
+        Ok(depth_stencil_state.unwrap())
+    }
+}
+
+#[derive(Clone)]
+struct BlendStates {
+    premultiplied: ID3D11BlendState,
+    straight: ID3D11BlendState,
+    additive: ID3D11BlendState,
+    additive_straight: ID3D11BlendState,
+    // for both alpha modes, see `PixelShaders::get`.
+    multiply: ID3D11BlendState,
+    // for `CompositeMode::Opaque`.
+    opaque: ID3D11BlendState,
+}
+
+impl BlendStates {
+    fn new(device: &ID3D11Device) -> Result<Self> {
+        let mut straight = Renderer::BLEND_DESC;
+        straight.RenderTarget[0].SrcBlend = D3D11_BLEND_SRC_ALPHA;
+        let mut additive = Renderer::BLEND_DESC;
+        additive.RenderTarget[0].DestBlend = D3D11_BLEND_ONE;
+        let mut additive_straight = additive;
+        additive_straight.RenderTarget[0].SrcBlend = D3D11_BLEND_SRC_ALPHA;
+        // dst * src + dst * (1 - src.a), i.e. dst * lerp(1, src, src.a) for
+        // unmultiplied colors.
+        let mut multiply = Renderer::BLEND_DESC;
+        multiply.RenderTarget[0].SrcBlend = D3D11_BLEND_DEST_COLOR;
+        multiply.RenderTarget[0].SrcBlendAlpha = D3D11_BLEND_ZERO;
+        let mut opaque = Renderer::BLEND_DESC;
+        opaque.RenderTarget[0].BlendEnable = false.into();
+        Ok(Self {
+            premultiplied: Self::create(device, &Renderer::BLEND_DESC)?,
+            straight: Self::create(device, &straight)?,
+            additive: Self::create(device, &additive)?,
+            additive_straight: Self::create(device, &additive_straight)?,
+            multiply: Self::create(device, &multiply)?,
+            opaque: Self::create(device, &opaque)?,
+        })
+    }
+
+    fn all(&self) -> [&ID3D11BlendState; 6] {
+        [
+            &self.premultiplied,
+            &self.straight,
+            &self.additive,
+            &self.additive_straight,
+            &self.multiply,
+            &self.opaque,
+        ]
+    }
+
+    fn create(
+        device: &ID3D11Device,
+        desc: &D3D11_BLEND_DESC,
+    ) -> Result<ID3D11BlendState> {
+        let mut blend_state = None;
+        unsafe { device.CreateBlendState(desc, Some(&mut blend_state)) }?;
+        Ok(blend_state.unwrap())
+    }
+
+    fn get(
+        &self,
+        composite_mode: CompositeMode,
+        alpha_mode: AlphaMode,
+        blend_mode: BlendMode,
+    ) -> &ID3D11BlendState {
+        if composite_mode == CompositeMode::Opaque {
+            return &self.opaque;
+        }
+        match (blend_mode, alpha_mode) {
+            (BlendMode::AlphaBlend, AlphaMode::Premultiplied) => {
+                &self.premultiplied
+            },
+            (BlendMode::AlphaBlend, AlphaMode::Straight) => &self.straight,
+            (BlendMode::Additive, AlphaMode::Premultiplied) => &self.additive,
+            (BlendMode::Additive, AlphaMode::Straight) => {
+                &self.additive_straight
+            },
+            (BlendMode::Multiply, _) => &self.multiply,
+        }
+    }
+}
+
+/// Draws meshes of a frame, keeping track of the bound blend state.
+struct MeshPainter<'a> {
+    device_context: &'a ID3D11DeviceContext,
+    texture_pool: &'a TexturePool,
+    sampler_cache: &'a mut SamplerCache,
+    pixel_shaders: &'a PixelShaders,
+    blend_states: &'a BlendStates,
+    draw_constant_buffer: &'a ID3D11Buffer,
+    draw_constants: DrawConstants,
+    scissor_enabled: bool,
+    manage_states: bool,
+    flip_y: bool,
+    composite_mode: CompositeMode,
+    texture_slot: u32,
+    transform: OutputTransform,
+    frame_size: (u32, u32),
+    // the modes of the bound blend state.
+    blend: (AlphaMode, BlendMode),
+    missing_texture_policy: MissingTexturePolicy,
+    missing_texture: Option<&'a ID3D11ShaderResourceView>,
+    warned_missing_textures: &'a mut HashSet<TextureId>,
+    // whether ids are drawn to an id buffer instead of colors.
+    id_pass: bool,
 }
 
-struct MeshData {
-    vtx: Vec<VertexData>,
-    idx: Vec<u32>,
-    tex: egui::TextureId,
-    clip_rect: egui::Rect,
+impl MeshPainter<'_> {
+    fn draw(&mut self, draw: &DrawCommand) -> Result<()> {
+        let device_context = self.device_context;
+        if self.scissor_enabled {
+            let mut scissor_rect = Renderer::scissor_rect(
+                draw.clip_rect,
+                self.transform,
+                self.frame_size,
+            );
+            if self.flip_y {
+                let height = self.frame_size.1 as i32;
+                (scissor_rect.top, scissor_rect.bottom) =
+                    (height - scissor_rect.bottom, height - scissor_rect.top);
+            }
+            unsafe { device_context.RSSetScissorRects(Some(&[scissor_rect])) };
+        }
+        let binding = match self.texture_pool.get(draw.texture_id) {
+            Some(binding) => binding,
+            None => match (self.missing_texture_policy, self.missing_texture) {
+                (MissingTexturePolicy::Magenta, Some(srv)) => {
+                    TextureBinding::new(srv.clone())
+                },
+                (MissingTexturePolicy::Warn, _) => {
+                    if self.warned_missing_textures.insert(draw.texture_id) {
+                        log::warn!(
+                            concat!(
+                                "egui wants to sample a non-existing texture {:?}. ",
+                                "this request will be ignored."
+                            ),
+                            draw.texture_id
+                        );
+                    }
+                    return Ok(());
+                },
+                _ => return Ok(()),
+            },
+        };
+        let draw_constants = DrawConstants {
+            emissive: binding.emissive,
+            draw_id: draw.draw_id,
+            texture_color_space: binding.color_space,
+            ..self.draw_constants
+        };
+        if draw_constants != self.draw_constants {
+            self.draw_constants = draw_constants;
+            Renderer::update_constant_buffer(
+                device_context,
+                self.draw_constant_buffer,
+                &draw_constants,
+            )?;
+        }
+        let sampler = match binding.custom_sampler.clone() {
+            Some(sampler) => Some(sampler),
+            None if self.manage_states => {
+                Some(self.sampler_cache.get(&binding.sampler)?)
+            },
+            None => None,
+        };
+        if let Some(sampler) = sampler {
+            unsafe { device_context.PSSetSamplers(0, Some(&[Some(sampler)])) };
+        }
+        unsafe {
+            if self.manage_states
+                && !self.id_pass
+                && (binding.alpha_mode, binding.blend_mode) != self.blend
+            {
+                self.blend = (binding.alpha_mode, binding.blend_mode);
+                device_context.OMSetBlendState(
+                    self.blend_states.get(
+                        self.composite_mode,
+                        binding.alpha_mode,
+                        binding.blend_mode,
+                    ),
+                    Some(&[0.; 4]),
+                    u32::MAX,
+                );
+            }
+            let pixel_shader = if self.id_pass {
+                &self.pixel_shaders.id
+            } else {
+                self.pixel_shaders.get(&binding)
+            };
+            device_context.PSSetShader(pixel_shader, None);
+            device_context.PSSetShaderResources(
+                self.texture_slot,
+                Some(&[
+                    Some(binding.srv),
+                    binding.video.map(|video| video.chroma_srv),
+                ]),
+            );
+        };
+        unsafe {
+            device_context.DrawIndexed(
+                draw.index_count,
+                draw.first_index,
+                draw.base_vertex,
+            )
+        };
+        Ok(())
+    }
 }
 
 impl Renderer {
@@ -121,62 +987,206 @@ impl Renderer {
     /// If any Direct3D resource creation fails, this function will return an
     /// error. You can create the Direct3D11 device with debug layer enabled
     /// to find out details on the error.
+    ///
+    /// Unless the `precompiled` feature is enabled, shaders are compiled at
+    /// this point, which takes a noticeable amount of time. See the
+    /// [crate-level documentation](crate#shader-compilation) for details.
     pub fn new(device: &ID3D11Device) -> Result<Self> {
+        Self::new_with_texture_pool(
+            device,
+            Arc::new(Mutex::new(TexturePool::new(device))),
+        )
+    }
+
+    /// Create a [`Renderer`] using the provided Direct3D11 device, sharing
+    /// the pool of textures with other [`Renderer`]s. See
+    /// [`SharedTexturePool`] for details.
+    ///
+    /// This function fails if `texture_pool` has been created on a different
+    /// device.
+    pub fn new_with_shared_texture_pool(
+        device: &ID3D11Device,
+        texture_pool: &SharedTexturePool,
+    ) -> Result<Self> {
+        let pool_device = texture_pool
+            .0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .device()
+            .as_raw();
+        if pool_device != device.as_raw() {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "the texture pool has been created on a different device",
+            ));
+        }
+        Self::new_with_texture_pool(device, texture_pool.0.clone())
+    }
+
+    /// The device this [`Renderer`] has been created with, e.g. for creating
+    /// textures to register with it. Clone it to keep a reference of your
+    /// own, which is cheap as Direct3D11 objects are reference counted.
+    pub fn device(&self) -> &ID3D11Device {
+        &self.device
+    }
+
+    /// The pool of textures used by this [`Renderer`], for sharing with other
+    /// [`Renderer`]s. See [`SharedTexturePool`] for details.
+    pub fn shared_texture_pool(&self) -> SharedTexturePool {
+        SharedTexturePool(self.texture_pool.clone())
+    }
+
+    /// Create a Direct3D11 device using WARP, the software rasterizer that
+    /// ships with Windows, along with its immediate context and a [`Renderer`]
+    /// using it.
+    ///
+    /// This is meant for rendering tests, e.g. comparing rendering results
+    /// against golden images on CI machines without a physical GPU. Rendering
+    /// with WARP is deterministic on the same version of Windows, but slow.
+    /// Only available with the `test-util` feature.
+    #[cfg(feature = "test-util")]
+    pub fn new_warp() -> Result<(ID3D11Device, ID3D11DeviceContext, Self)> {
+        use windows::Win32::Foundation::HMODULE;
+
+        let mut device = None;
+        let mut device_context = None;
+        unsafe {
+            D3D11CreateDevice(
+                None,
+                D3D_DRIVER_TYPE_WARP,
+                HMODULE::default(),
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                None,
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None,
+                Some(&mut device_context),
+            )
+        }?;
+        let device = device.unwrap();
+        let renderer = Self::new(&device)?;
+        Ok((device, device_context.unwrap(), renderer))
+    }
+
+    /// Bring the internal state of the [`Renderer`] back to the state of a
+    /// newly created one, so that every rendering test starts from the same
+    /// state regardless of the tests run before it, e.g. for comparing
+    /// against golden images.
+    ///
+    /// This drops the geometry buffers (so [`Renderer::render_cached`] draws
+    /// nothing until the next [`Renderer::render`]), the cached render target
+    /// views, samplers and rasterizer state, the textures kept after being
+    /// freed by `egui`, the record of [`Renderer::last_freed_textures`], and
+    /// the record of missing textures already warned about.
+    /// The constant buffers are fully rewritten by every draw, so they never
+    /// carry over. Settings, e.g. from [`Renderer::set_preserve_state`], are
+    /// kept, as well as registered native textures and textures managed by
+    /// `egui`, which a new `egui` context replaces when it first sends its
+    /// font atlas. Use [`Renderer::clear_native_textures`] to remove native
+    /// textures.
+    ///
+    /// This is not meant for production use, as the dropped resources are
+    /// recreated on the next frames. Only available with the `test-util`
+    /// feature.
+    #[cfg(feature = "test-util")]
+    pub fn reset_for_test(&mut self) {
+        self.geometry = GeometryWriter::new();
+        self.geometry_pixels_per_point = 1.;
+        self.render_target_cache.clear();
+        self.sampler_cache = SamplerCache::new(&self.device);
+        self.rasterizer_state = None;
+        self.last_freed_textures.clear();
+        self.warned_missing_textures.clear();
+        self.mismatched_frame_size = None;
+        self.screen_size = [0.; 2];
+        self.lock_texture_pool().clear_freed_textures();
+    }
+
+    fn new_with_texture_pool(
+        device: &ID3D11Device,
+        texture_pool: Arc<Mutex<TexturePool>>,
+    ) -> Result<Self> {
         let mut input_layout = None;
         let mut vertex_shader = None;
-        let mut pixel_shader = None;
-        let mut rasterizer_state = None;
-        let mut sampler_state = None;
-        let mut blend_state = None;
+        let vs_blob = shader::VS_EGUI.bytecode()?;
         unsafe {
             device.CreateInputLayout(
                 &Self::INPUT_ELEMENTS_DESC,
-                Self::VS_BLOB,
+                &vs_blob,
                 Some(&mut input_layout),
             )?;
             device.CreateVertexShader(
-                Self::VS_BLOB,
+                &vs_blob,
                 None,
                 Some(&mut vertex_shader),
             )?;
-            device.CreatePixelShader(
-                Self::PS_BLOB,
-                None,
-                Some(&mut pixel_shader),
-            )?;
-            device.CreateRasterizerState(
-                &Self::RASTERIZER_DESC,
-                Some(&mut rasterizer_state),
-            )?;
-            device.CreateSamplerState(
-                &Self::SAMPLER_DESC,
-                Some(&mut sampler_state),
-            )?;
-            device
-                .CreateBlendState(&Self::BLEND_DESC, Some(&mut blend_state))?;
         };
+        let constant_buffer =
+            Self::create_constant_buffer(device, mem::size_of::<Constants>())?;
+        let draw_constant_buffer = Self::create_constant_buffer(
+            device,
+            mem::size_of::<DrawConstants>(),
+        )?;
+        let rasterizer_state =
+            Self::create_rasterizer_state(device, &Self::RASTERIZER_DESC)?;
         Ok(Self {
             device: device.clone(),
             input_layout: input_layout.unwrap(),
             vertex_shader: vertex_shader.unwrap(),
-            pixel_shader: pixel_shader.unwrap(),
-            rasterizer_state: rasterizer_state.unwrap(),
-            sampler_state: sampler_state.unwrap(),
-            blend_state: blend_state.unwrap(),
-            texture_pool: TexturePool::new(device),
+            pixel_shaders: PixelShaders::new(device, 0)?,
+            texture_slot: 0,
+            rasterizer_desc: Self::RASTERIZER_DESC,
+            rasterizer_state: Some(rasterizer_state),
+            sampler_cache: SamplerCache::new(device),
+            blend_states: BlendStates::new(device)?,
+            depth_func: DepthFunc::Always,
+            depth_states: DepthStates::new(device, DepthFunc::Always)?,
+            constant_buffer,
+            draw_constant_buffer,
+            callback_constants: Vec::new(),
+            callback_constant_buffer: None,
+            blitter: None,
+            geometry: GeometryWriter::new(),
+            geometry_pixels_per_point: 1.,
+            gpu_timer: None,
+            cpu_timing: false,
+            last_cpu_time_us: None,
+            texture_pool,
+            render_target_cache: RenderTargetCache::new(),
+            last_freed_textures: Vec::new(),
+            missing_texture_policy: MissingTexturePolicy::Warn,
+            missing_texture: None,
+            warned_missing_textures: HashSet::new(),
+            preserve_state: false,
+            manage_states: true,
+            flip_y: false,
+            composite_mode: CompositeMode::Blend,
+            validate: false,
+            projection_override: None,
+            output_transform: None,
+            layer_opacity: 1.,
+            color_matrix: None,
+            vertex_color_space: VertexColorSpace::Gamma,
+            output_color_space: DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+            mismatched_frame_size: None,
+            screen_size: [0.; 2],
         })
     }
 
     /// Render the output of `egui` to the provided `render_target`.
     ///
-    /// As `egui` requires color blending in gamma space, **the provided
-    /// `render_target` MUST be in the gamma color space and viewed as
-    /// non-sRGB-aware** (i.e. do NOT use `_SRGB` format in the texture and
-    /// the view).
+    /// As `egui` requires color blending in gamma space, the provided
+    /// `render_target` should be in the gamma color space and viewed as
+    /// non-sRGB-aware (i.e. do NOT use `_SRGB` format in the texture and the
+    /// view) for results to be accurate.
     ///
-    /// If you have to render to a render target in linear color space or
-    /// one that is sRGB-aware, you must create an intermediate render target
-    /// in gamma color space and perform a blit operation afterwards.
+    /// If `render_target` is viewed with an `_SRGB` format, the hardware
+    /// encodes written colors to gamma space itself, so the renderer outputs
+    /// colors in linear space instead to avoid encoding them twice. Note that
+    /// color blending then happens in linear space, so translucent areas look
+    /// slightly different from what `egui` intends. If this is not
+    /// acceptable, create an intermediate render target in gamma color space
+    /// and perform a blit operation afterwards.
     ///
     /// The `scale_factor` should be the scale factor of your window and not
     /// confused with [`egui::Context::zoom_factor`]. If you are using `winit`,
@@ -184,12 +1194,36 @@ impl Renderer {
     ///
     /// ## Error Handling
     ///
+    /// If `render_target` is not a view of a live Texture2D, this function
+    /// returns an error without rendering anything, though texture updates
+    /// are still applied. If the size of
+    /// `render_target` does not match the screen size of `egui_ctx`, which
+    /// usually means that a stale render target is used during resizing, a
+    /// warning is logged.
+    ///
     /// If any Direct3D resource creation fails, this function will return an
     /// error. In this case you may have a incomplete or incorrect rendering
     /// result. You can create the Direct3D11 device with debug layer
     /// enabled to find out details on the error.
     /// If the device has been lost, you should drop the [`Renderer`] and create
-    /// a new one.
+    /// a new one. If the geometry of a frame is too large for the buffers the
+    /// device supports, the returned error names the number of vertices or
+    /// indices to draw, and a warning is logged as soon as it exceeds the size
+    /// every device supports (128 MiB per buffer).
+    ///
+    /// ## Interop with Direct2D
+    ///
+    /// When Direct2D draws to the same texture, render `egui` before
+    /// `ID2D1RenderTarget::BeginDraw` or after `ID2D1RenderTarget::EndDraw`,
+    /// never in between, as Direct2D may defer its drawing until `EndDraw`.
+    /// The shader resources bound by this function are unbound on return.
+    ///
+    /// ## Draw Order
+    ///
+    /// Meshes are drawn strictly in the order `egui` tessellates them, without
+    /// batching, reordering or a depth buffer, so `egui` is never occluded.
+    /// See [`Renderer::render_with_depth`] and
+    /// [`Renderer::render_with_stencil_mask`] for testing against a scene.
     ///
     /// ## Pipeline State Management
     ///
@@ -198,108 +1232,2228 @@ impl Renderer {
     /// shader and geometry shader stages are not active on the provided device
     /// context without any further checks. It is all *your* responsibility to
     /// backup the current pipeline state and restore it afterwards if your
-    /// rendering pipeline depends on it.
+    /// rendering pipeline depends on it, unless you have enabled
+    /// [`Renderer::set_preserve_state`].
     ///
     /// Particularly, it overrides:
     /// + The input layout, vertex buffer, index buffer and primitive topology
     ///   in the input assembly stage;
-    /// + The current shader in the vertex shader stage;
+    /// + The current shader and constant buffer slot 0 in the vertex shader
+    ///   stage;
     /// + The viewport and rasterizer state in the rasterizer stage;
-    /// + The current shader, shader resource slot 0 and sampler slot 0 in the
-    ///   pixel shader stage;
-    /// + The render target(s) and blend state in the output merger stage;
+    /// + The current shader, constant buffer slots 0 to 2, shader resource
+    ///   slots 0 and 1 (see [`Renderer::set_texture_slot`]), and sampler
+    ///   slot 0 in the pixel shader stage;
+    /// + The render target(s), depth stencil view (unbound), depth stencil
+    ///   state and blend state in the output merger stage;
+    ///
+    /// Both shader resource slots are unbound again before returning.
+    /// [`egui::PaintCallback`]s are not supported yet, and are skipped with a
+    /// warning.
+    ///
+    /// ## Deferred Contexts
+    ///
+    /// `device_context` may be a deferred context. Everything, including
+    /// texture updates, is recorded into it and takes effect when its command
+    /// list is executed, so command lists must be executed in the order they
+    /// were recorded. The device must then not be single-threaded if the
+    /// context is recorded on another thread.
+    ///
+    /// ## Dirty Rectangle
+    ///
+    /// On success, the bounding rectangle of everything drawn is returned in
+    /// pixels of `render_target`, e.g. for `IDXGISwapChain1::Present1`. If
+    /// nothing is drawn, an empty rectangle with all fields `0` is returned,
+    /// which must not be passed as a dirty rectangle. `None` means the whole
+    /// frame, and is returned if the drawn area covers the render target, or
+    /// with [`Renderer::set_projection_override`].
     pub fn render(
         &mut self,
         device_context: &ID3D11DeviceContext,
         render_target: &ID3D11RenderTargetView,
         egui_ctx: &egui::Context,
         egui_output: RendererOutput,
-    ) -> Result<()> {
-        self.texture_pool
-            .update(device_context, egui_output.textures_delta)?;
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions::default(),
+            )
+        })
+    }
 
-        if egui_output.shapes.is_empty() {
-            return Ok(());
-        }
+    /// Render the output of `egui` like [`Renderer::render`], at the provided
+    /// `pixels_per_point` instead of [`RendererOutput::pixels_per_point`],
+    /// e.g. while a window moves between monitors of different DPI and the
+    /// scale is only known when rendering.
+    ///
+    /// `pixels_per_point` is used for tessellation, the projection and the
+    /// conversion of clip rectangles to scissor rectangles, and takes
+    /// precedence over the pixels per point of `egui_output` and `egui_ctx`
+    /// for this call only. `egui` rasterizes text at its own pixels per
+    /// point, so text may look blurry until `egui_ctx` uses the same scale.
+    /// A projection matrix set with [`Renderer::set_projection_override`]
+    /// still takes precedence over the computed projection.
+    pub fn render_scaled(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        egui_ctx: &egui::Context,
+        mut egui_output: RendererOutput,
+        pixels_per_point: f32,
+    ) -> Result<Option<RECT>> {
+        egui_output.pixels_per_point = pixels_per_point;
+        self.render(device_context, render_target, egui_ctx, egui_output)
+    }
 
-        let frame_size = Self::get_render_target_size(render_target)?;
-        let frame_size_scaled = (
-            frame_size.0 as f32 / egui_output.pixels_per_point,
-            frame_size.1 as f32 / egui_output.pixels_per_point,
-        );
-        let zoom_factor = egui_ctx.zoom_factor();
-
-        self.setup(device_context, render_target, frame_size);
-        let meshes = egui_ctx
-            .tessellate(egui_output.shapes, egui_output.pixels_per_point)
-            .into_iter()
-            .filter_map(
-                |ClippedPrimitive {
-                     primitive,
-                     clip_rect,
-                 }| match primitive {
-                    Primitive::Mesh(mesh) => Some((mesh, clip_rect)),
-                    Primitive::Callback(..) => {
-                        log::warn!("paint callbacks are not yet supported.");
-                        None
-                    },
-                },
+    /// Render the output of `egui` on top of the existing content of
+    /// `render_target`, typically the back buffer of a swap chain whose
+    /// `IDXGISwapChain::Present` has been hooked.
+    ///
+    /// This is a preset for the most common overlay use case. It behaves like
+    /// [`Renderer::render`] with [`Renderer::set_preserve_state`] enabled,
+    /// regardless of the current setting: the pipeline state of
+    /// `device_context` is restored on return, so that rendering of the host
+    /// application is not affected. As with [`Renderer::render`], the render
+    /// target is never cleared and `egui` is composited over its existing
+    /// content with regular alpha blending.
+    ///
+    /// The returned dirty rectangle is the same as for [`Renderer::render`].
+    pub fn render_overlay(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, true, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions::default(),
             )
-            .filter_map(|(mesh, clip_rect)| {
-                if mesh.indices.is_empty() {
-                    return None;
-                }
-                if mesh.indices.len() % 3 != 0 {
-                    log::warn!(concat!(
-                        "egui wants to draw a incomplete triangle. ",
-                        "this request will be ignored."
-                    ));
-                    return None;
-                }
-                Some(MeshData {
-                    vtx: mesh
-                        .vertices
-                        .into_iter()
-                        .map(|Vertex { pos, uv, color }| VertexData {
-                            pos: Pos2::new(
-                                pos.x * zoom_factor / frame_size_scaled.0 * 2.0
-                                    - 1.0,
-                                1.0 - pos.y * zoom_factor / frame_size_scaled.1
-                                    * 2.0,
-                            ),
-                            uv,
-                            color: [
-                                color[0] as f32 / 255.0,
-                                color[1] as f32 / 255.0,
-                                color[2] as f32 / 255.0,
-                                color[3] as f32 / 255.0,
-                            ],
-                        })
-                        .collect(),
-                    idx: mesh.indices,
-                    tex: mesh.texture_id,
-                    clip_rect: clip_rect
-                        * egui_output.pixels_per_point
-                        * zoom_factor,
-                })
+        })
+    }
+
+    /// Draw the geometry of the last output passed to [`Renderer::render`] (or
+    /// one of its variants) again, to the provided `render_target`.
+    ///
+    /// Neither the `egui` context is run nor is anything tessellated or
+    /// uploaded again, as the geometry is kept on the GPU, which saves CPU
+    /// time for mostly static user interfaces. Textures are drawn in their
+    /// current state, as updated by the last [`Renderer::render`] or
+    /// [`Renderer::update_textures`]. Call [`Renderer::render`] with a new
+    /// output from `egui` as soon as it has something to change, e.g. when
+    /// [`egui::Context::has_requested_repaint`] returns `true`. Nothing is
+    /// drawn before the first call to [`Renderer::render`].
+    ///
+    /// The requirements on the render target and the pipeline state
+    /// management are the same as for [`Renderer::render`].
+    pub fn render_cached(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+    ) -> Result<()> {
+        if self.geometry.is_empty() {
+            return Ok(());
+        }
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            let mut geometry = mem::take(&mut this.geometry);
+            let result = this.draw_geometry(
+                device_context,
+                render_target,
+                None,
+                &mut geometry,
+                this.geometry_pixels_per_point,
+                None,
+            );
+            this.geometry = geometry;
+            result.map(|_| ())
+        })
+    }
+
+    /// Make sure the CPU-side staging memory for the geometry of
+    /// [`Renderer::render`] and its variants can hold at least `vertices`
+    /// vertices and `indices` indices without reallocating.
+    ///
+    /// The staging memory is reused across frames and grows to fit the
+    /// largest frame, so nothing is allocated per frame once the geometry has
+    /// stopped growing. Reserving the expected capacity ahead of time avoids
+    /// the reallocations while it grows. See [`GeometryWriter::reserve`] for
+    /// geometry drawn with [`Renderer::draw_appended`].
+    pub fn set_staging_capacity(&mut self, vertices: usize, indices: usize) {
+        self.geometry.reserve(vertices, indices);
+    }
+
+    /// Set the number of sets of geometry buffers [`Renderer::render`] and
+    /// its variants rotate between, one for each frame. Defaults to `1`,
+    /// i.e. a single set that is discarded when it is written. See
+    /// [`GeometryWriter::set_frames_in_flight`] for the requirements and
+    /// the cost in video memory of more sets.
+    pub fn set_frames_in_flight(&mut self, frames_in_flight: usize) {
+        self.geometry.set_frames_in_flight(frames_in_flight);
+    }
+
+    /// Shrink the geometry buffers of [`Renderer::render`] and its variants
+    /// once `frames` frames in a row have used less than `ratio` of their
+    /// capacity, so that a single huge frame does not inflate them for good.
+    /// Defaults to a `frames` of `0`, i.e. the buffers stay at the size of
+    /// the largest frame and are never shrunk, which avoids reallocating them
+    /// when the geometry is spiky. See [`GeometryWriter::set_shrink_policy`]
+    /// for details.
+    pub fn set_buffer_shrink_policy(&mut self, frames: usize, ratio: f32) {
+        self.geometry.set_shrink_policy(frames, ratio);
+    }
+
+    /// Render the output of `egui` like [`Renderer::render`], and additionally
+    /// write an id for each tessellated primitive to `id_buffer`, as returned
+    /// by `draw_id`, e.g. for picking widgets on the GPU in an editor.
+    ///
+    /// `draw_id` is called once for every primitive in drawing order, and may
+    /// derive the id from its clip rectangle or texture, or from the order of
+    /// the primitives. See [`Renderer::draw_appended_with_id_buffer`] for how
+    /// ids are drawn and for the requirements on `id_buffer`. The returned
+    /// dirty rectangle is the same as for [`Renderer::render`], and bounds the
+    /// ids written as well.
+    pub fn render_with_id_buffer(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        id_buffer: &ID3D11RenderTargetView,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+        mut draw_id: impl FnMut(&ClippedPrimitive) -> u32,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions {
+                    id_buffer: Some((id_buffer, &mut draw_id)),
+                    ..Default::default()
+                },
+            )
+        })
+    }
+
+    /// Render the output of `egui` like [`Renderer::render`], testing it
+    /// against the depth buffer of `depth_stencil` with the function set with
+    /// [`Renderer::set_depth_func`], e.g. for occluding a UI placed in a 3D
+    /// scene with [`Renderer::set_projection_override`] by the scene. The
+    /// depth of `egui` is the `z` its projection yields, i.e. `0.0` for the
+    /// default projection. The depth buffer is never written.
+    ///
+    /// `depth_stencil` must be a view of a texture of a depth format of the
+    /// same size and sample count as `render_target`. Besides the state
+    /// overridden by [`Renderer::render`], this binds `depth_stencil`.
+    pub fn render_with_depth(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        depth_stencil: &ID3D11DepthStencilView,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions {
+                    depth_stencil: Some((depth_stencil, None)),
+                    ..Default::default()
+                },
+            )
+        })
+    }
+
+    /// Set how the depth of `egui` is compared against the depth buffer of
+    /// [`Renderer::render_with_depth`] and
+    /// [`Renderer::render_with_stencil_mask`]. Defaults to
+    /// [`DepthFunc::Always`], i.e. `egui` is never occluded.
+    ///
+    /// Use [`DepthFunc::LessEqual`] for scenes with the conventional depth
+    /// range, and [`DepthFunc::GreaterEqual`] for scenes with reversed-Z
+    /// depth. Without a depth buffer, e.g. for [`Renderer::render`], the
+    /// function has no effect.
+    ///
+    /// The depth stencil states are created right away, so this function
+    /// fails if they can not be created, in which case the current function
+    /// is kept.
+    pub fn set_depth_func(&mut self, depth_func: DepthFunc) -> Result<()> {
+        if depth_func != self.depth_func {
+            self.depth_states = DepthStates::new(&self.device, depth_func)?;
+            self.depth_func = depth_func;
+        }
+        Ok(())
+    }
+
+    /// Render the output of `egui` like [`Renderer::render`], drawing only
+    /// where the stencil buffer of `depth_stencil` equals `stencil_ref`, e.g.
+    /// for clipping a whole window to a rounded rectangle or an arbitrary
+    /// mask for a custom skin. `egui` itself only clips to rectangles.
+    ///
+    /// Populate the stencil buffer before calling this function, e.g. by
+    /// clearing it to 0 with `ID3D11DeviceContext::ClearDepthStencilView` and
+    /// drawing the mask shape with a depth stencil state whose
+    /// `StencilPassOp` is `D3D11_STENCIL_OP_REPLACE`, a stencil reference of
+    /// `stencil_ref`, and color writes disabled in the blend state. The
+    /// stencil buffer is tested against with all bits of `stencil_ref`, and is
+    /// never written by the [`Renderer`]. The depth buffer is tested against
+    /// as set with [`Renderer::set_depth_func`], which defaults to
+    /// [`DepthFunc::Always`], i.e. the depth buffer is ignored by default.
+    ///
+    /// `depth_stencil` must be a view of a texture of a depth stencil format,
+    /// e.g. `DXGI_FORMAT_D24_UNORM_S8_UINT`, of the same size and sample count
+    /// as `render_target`. Besides the state overridden by
+    /// [`Renderer::render`], this binds `depth_stencil` and overrides the
+    /// depth stencil state and stencil reference of the output merger stage.
+    pub fn render_with_stencil_mask(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        depth_stencil: &ID3D11DepthStencilView,
+        stencil_ref: u8,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions {
+                    depth_stencil: Some((depth_stencil, Some(stencil_ref))),
+                    ..Default::default()
+                },
+            )
+        })
+    }
+
+    /// Render the output of `egui` like [`Renderer::render`], drawing only the
+    /// tessellated primitives for which `filter` returns `true`, e.g. for
+    /// compositing tooltips over game content in a separate pass from the
+    /// panels behind it. Excluded primitives are skipped, as if `egui` had not
+    /// drawn them, while texture updates are applied as usual.
+    ///
+    /// `filter` is called once for every primitive in drawing order. The
+    /// output of `egui` does not tell which layer a primitive belongs to, but
+    /// the primitives of a layer are clipped to its area, so layers may be
+    /// told apart by the clip rectangles of the primitives, e.g. against
+    /// [`egui::Memory::area_rect`] of the layer. To draw the other primitives
+    /// of the same frame in another pass, clone the shapes of the output
+    /// before the first pass, and render them along with an empty
+    /// [`egui::TexturesDelta`], as texture updates must be applied only once.
+    pub fn render_filtered(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+        mut filter: impl FnMut(&ClippedPrimitive) -> bool,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.render_frame(
+                device_context,
+                render_target,
+                egui_ctx,
+                egui_output,
+                FrameOptions {
+                    filter: Some(&mut filter),
+                    ..Default::default()
+                },
+            )
+        })
+    }
+
+    /// Draw already tessellated `primitives` to `render_target`, applying
+    /// `textures_delta` first, e.g. for meshes cached by a custom painter
+    /// that should not be tessellated again by [`egui::Context::tessellate`].
+    ///
+    /// This is the lower-level entry point below [`Renderer::render`], which
+    /// tessellates the shapes of its output and draws them the same way. The
+    /// positions of `primitives` are in points, and are converted to pixels
+    /// with `pixels_per_point`, which should be the value the primitives
+    /// have been tessellated with. Pass an empty [`egui::TexturesDelta`] if
+    /// there are no texture updates, as they must be applied only once.
+    ///
+    /// The primitives are drawn with the geometry buffers of
+    /// [`Renderer::render`], so [`Renderer::render_cached`] draws them again
+    /// afterwards. The requirements on the render target, the pipeline state
+    /// management and the returned dirty rectangle are the same as for
+    /// [`Renderer::render`].
+    pub fn render_meshes(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        primitives: &[ClippedPrimitive],
+        textures_delta: TexturesDelta,
+        pixels_per_point: f32,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.update_textures(device_context, textures_delta)?;
+            this.draw_primitives(
+                device_context,
+                render_target,
+                primitives,
+                pixels_per_point,
+                None,
+                None,
+            )
+        })
+    }
+
+    /// Draw raw tessellated geometry to `render_target`, for hosts that can
+    /// not construct [`egui::ClippedPrimitive`]s, e.g. other languages
+    /// binding this crate over FFI. This is the lowest-level draw entry point,
+    /// and draws like [`Renderer::render_meshes`] otherwise. The arguments
+    /// map to plain pointers and lengths, e.g. through `slice::from_raw_parts`
+    /// in an `extern "C"` wrapper. This crate does not provide such a wrapper,
+    /// so the FFI layer, including how the [`Renderer`] and the Direct3D11
+    /// objects are passed across it, is left to the host.
+    ///
+    /// `vertices` holds tightly packed vertices of [`RAW_VERTEX_SIZE`] bytes
+    /// in the layout of [`egui::epaint::Vertex`], in native byte order:
+    ///
+    /// ```c
+    /// struct Vertex {
+    ///     float   pos[2];   // position in points, from the top left corner
+    ///     float   uv[2];    // texture coordinates, from 0 to 1
+    ///     uint8_t color[4]; // premultiplied RGBA in gamma space
+    /// };
+    /// ```
+    ///
+    /// `indices` holds `uint32_t` indices into `vertices`, three per
+    /// triangle, and may be unaligned. Each of `draws` draws `index_count`
+    /// indices starting at `index_offset`, sampling its texture and clipped to
+    /// its clip rectangle in points, in order. Positions and clip rectangles
+    /// are converted to pixels with `pixels_per_point`, so a position of
+    /// `(0, 0)` is the top left corner of the render target and one of its
+    /// size divided by `pixels_per_point` the bottom right corner.
+    ///
+    /// This function fails if the buffers do not consist of whole vertices
+    /// and indices. Draws whose indices are out of range are skipped with an
+    /// error in the log. Textures are not updated, so register them as native
+    /// textures, or apply deltas with [`Renderer::update_textures`].
+    pub fn render_raw(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        vertices: &[u8],
+        indices: &[u8],
+        draws: &[RawDrawCommand],
+        pixels_per_point: f32,
+    ) -> Result<Option<RECT>> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            let mut geometry = mem::take(&mut this.geometry);
+            geometry.clear();
+            let result = geometry
+                .append_raw(vertices, indices, draws, this.validate)
+                .and_then(|()| {
+                    this.geometry_pixels_per_point = pixels_per_point;
+                    this.draw_geometry(
+                        device_context,
+                        render_target,
+                        None,
+                        &mut geometry,
+                        pixels_per_point,
+                        None,
+                    )
+                });
+            this.geometry = geometry;
+            result
+        })
+    }
+
+    /// Set whether [`Renderer::render`] should backup the pipeline state of
+    /// the provided device context and restore it afterwards. Disabled by
+    /// default.
+    ///
+    /// This saves you from managing the pipeline state yourself, at the cost
+    /// of a few extra calls to the device context per frame. Note that class
+    /// instances bound to the vertex and pixel shader stages are not
+    /// preserved. The pipeline state of a deferred context starts out cleared
+    /// for each command list, so only what has been set on the deferred
+    /// context itself is restored.
+    pub fn set_preserve_state(&mut self, preserve_state: bool) {
+        self.preserve_state = preserve_state;
+    }
+
+    /// Set whether the [`Renderer`] binds its own rasterizer state, blend state
+    /// and samplers when rendering. Enabled by default.
+    ///
+    /// When disabled, only the shaders, buffers, shader resource views,
+    /// viewport and scissor rectangles are bound, and the states currently
+    /// bound to the device context are used as is, e.g. for engines managing
+    /// all pipeline states in a unified cache. It is then *your*
+    /// responsibility to bind states compatible with `egui`:
+    /// + A blend state for premultiplied alpha, i.e. `D3D11_BLEND_ONE` as
+    ///   source and `D3D11_BLEND_INV_SRC_ALPHA` as destination blend factor
+    ///   for color, and `D3D11_BLEND_INV_DEST_ALPHA` and `D3D11_BLEND_ONE`
+    ///   for alpha;
+    /// + A linear sampler clamping texture coordinates in sampler slot 0;
+    /// + A rasterizer state without culling, with the scissor test enabled
+    ///   for clipping widgets.
+    ///
+    /// Otherwise rendering will look wrong. Options of textures which affect
+    /// these states, i.e. their sampler options, [`AlphaMode::Straight`] and
+    /// their [`BlendMode`], are ignored.
+    pub fn set_manage_states(&mut self, manage_states: bool) {
+        self.manage_states = manage_states;
+    }
+
+    /// Set what happens when `egui` draws with a texture the [`Renderer`] does
+    /// not know, e.g. a native texture removed while an image still shows it,
+    /// or an id that has never been registered. Defaults to
+    /// [`MissingTexturePolicy::Warn`].
+    ///
+    /// [`MissingTexturePolicy::Magenta`] helps finding such images while
+    /// debugging, as they stand out on screen. Use
+    /// [`Renderer::contains_texture`] to check ids ahead of drawing instead.
+    pub fn set_missing_texture_policy(&mut self, policy: MissingTexturePolicy) {
+        self.missing_texture_policy = policy;
+    }
+
+    /// Set whether meshes are validated before drawing, e.g. while debugging
+    /// custom meshes. Disabled by default.
+    ///
+    /// When enabled, meshes with indices referring to vertices beyond their
+    /// own vertices, or with clip rectangles that are not finite or have a
+    /// negative size, are skipped with an error logged, instead of being fed
+    /// to the GPU. This costs a pass over all indices every frame, so it is
+    /// meant for debug builds, e.g. with `set_validate(cfg!(debug_assertions))`.
+    pub fn set_validate(&mut self, validate: bool) {
+        self.validate = validate;
+    }
+
+    /// Backup and restore the pipeline state around `render` if requested,
+    /// and measure the CPU and GPU time of it if enabled.
+    fn render_wrapped<T>(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        preserve_state: bool,
+        render: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let start = self.cpu_timing.then(Instant::now);
+        let texture_slot = self.texture_slot;
+        let backup = preserve_state
+            .then(|| StateBackup::save(device_context, texture_slot));
+        // queries can not be read back from deferred contexts. a failed
+        // measurement must not skip restoring the backup, so the frame is
+        // rendered without it.
+        let gpu_timer = unsafe { device_context.GetType() }
+            == D3D11_DEVICE_CONTEXT_IMMEDIATE
+            && self.gpu_timer.as_mut().is_some_and(|gpu_timer| {
+                gpu_timer
+                    .begin(device_context)
+                    .inspect_err(|err| {
+                        log::warn!(
+                            "failed to start measuring the gpu time ({err}). this frame will not be measured."
+                        );
+                    })
+                    .is_ok()
             });
-        for mesh in meshes {
-            Self::draw_mesh(
-                &self.device,
+        let result = render(self);
+        if gpu_timer && let Some(gpu_timer) = &mut self.gpu_timer {
+            gpu_timer.end(device_context);
+        }
+        if let Some(backup) = backup {
+            backup.restore(device_context);
+        }
+        if let Some(start) = start {
+            self.last_cpu_time_us = Some(start.elapsed().as_micros() as _);
+        }
+        result
+    }
+
+    fn render_frame(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+        options: FrameOptions,
+    ) -> Result<Option<RECT>> {
+        // apply texture deltas first, as egui never sends them again.
+        self.update_textures(device_context, egui_output.textures_delta)?;
+
+        let frame_size = Self::get_render_target_size(render_target)?;
+        // the canvas is scaled to the frame by `render_to_texture_fit`.
+        if self.output_transform.is_none() {
+            self.check_frame_size(
+                frame_size,
+                egui_ctx.screen_rect().size() * egui_output.pixels_per_point,
+            );
+        }
+
+        if egui_output.shapes.is_empty() {
+            self.geometry.clear();
+            return Ok(Some(RECT::default()));
+        }
+
+        // `pixels_per_point` already includes the zoom factor of `egui_ctx`.
+        let pixels_per_point = egui_output.pixels_per_point;
+        let mut primitives =
+            egui_ctx.tessellate(egui_output.shapes, pixels_per_point);
+        if let Some(filter) = options.filter {
+            primitives.retain(|primitive| filter(primitive));
+        }
+        self.draw_primitives(
+            device_context,
+            render_target,
+            &primitives,
+            pixels_per_point,
+            options.id_buffer,
+            options.depth_stencil,
+        )
+    }
+
+    /// Draw `primitives` with the geometry buffers of the [`Renderer`], so
+    /// that they can be drawn again by [`Renderer::render_cached`].
+    fn draw_primitives(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        primitives: &[ClippedPrimitive],
+        pixels_per_point: f32,
+        id_buffer: Option<IdBuffer>,
+        depth_stencil: Option<DepthStencil>,
+    ) -> Result<Option<RECT>> {
+        // reuse the buffers of the geometry from the last frame.
+        let mut geometry = mem::take(&mut self.geometry);
+        geometry.clear();
+        let id_target = match id_buffer {
+            Some((id_target, draw_id)) => {
+                for primitive in primitives {
+                    geometry.set_draw_id(draw_id(primitive));
+                    geometry.append([primitive], self.validate);
+                }
+                Some(id_target)
+            },
+            None => {
+                geometry.append(primitives, self.validate);
+                None
+            },
+        };
+        self.geometry_pixels_per_point = pixels_per_point;
+        let result = self.draw_geometry(
+            device_context,
+            render_target,
+            id_target,
+            &mut geometry,
+            pixels_per_point,
+            depth_stencil,
+        );
+        self.geometry = geometry;
+        result
+    }
+
+    fn draw_geometry(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        id_target: Option<&ID3D11RenderTargetView>,
+        geometry: &mut GeometryWriter,
+        pixels_per_point: f32,
+        depth_stencil: Option<DepthStencil>,
+    ) -> Result<Option<RECT>> {
+        let frame_size = Self::get_render_target_size(render_target)?;
+        if let Some(id_target) = id_target
+            && Self::get_render_target_size(id_target)? != frame_size
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "the id buffer differs in size from the render target",
+            ));
+        }
+        let transform = self
+            .output_transform
+            .unwrap_or(OutputTransform::uniform(pixels_per_point, frame_size));
+        self.screen_size = transform.size.into();
+
+        let rasterizer_state = self.rasterizer_state()?;
+        // the rasterizer state of the caller may enable the scissor test.
+        let scissor_enabled =
+            self.rasterizer_desc.ScissorEnable.as_bool() || !self.manage_states;
+
+        if self.missing_texture_policy == MissingTexturePolicy::Magenta
+            && self.missing_texture.is_none()
+        {
+            self.missing_texture =
+                Some(Self::create_missing_texture(&self.device)?);
+        }
+        let texture_pool = self
+            .texture_pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        self.setup(
+            device_context,
+            render_target,
+            &rasterizer_state,
+            transform.viewport(frame_size.1, self.flip_y),
+        );
+        if let Some((view, stencil_ref)) = depth_stencil {
+            unsafe {
+                device_context.OMSetRenderTargets(
+                    Some(&[Some(render_target.clone())]),
+                    view,
+                );
+                match stencil_ref {
+                    Some(stencil_ref) => device_context.OMSetDepthStencilState(
+                        &self.depth_states.stencil,
+                        stencil_ref as _,
+                    ),
+                    None => device_context
+                        .OMSetDepthStencilState(&self.depth_states.depth, 0),
+                }
+            }
+        }
+        self.update_constants(
+            device_context,
+            &Constants {
+                projection: self.projection_matrix(),
+                output_encoding: self.output_encoding(render_target),
+                opacity: self.layer_opacity,
+                vertex_color_space: self.vertex_color_space,
+                use_color_matrix: self.color_matrix.is_some() as _,
+                color_matrix: self.color_matrix.unwrap_or_default(),
+            },
+        )?;
+        geometry.bind(&self.device, device_context)?;
+        let mut painter = MeshPainter {
+            device_context,
+            texture_pool: &texture_pool,
+            sampler_cache: &mut self.sampler_cache,
+            pixel_shaders: &self.pixel_shaders,
+            blend_states: &self.blend_states,
+            draw_constant_buffer: &self.draw_constant_buffer,
+            // uploaded by `update_constants`.
+            draw_constants: DrawConstants::default(),
+            scissor_enabled,
+            manage_states: self.manage_states,
+            flip_y: self.flip_y,
+            composite_mode: self.composite_mode,
+            texture_slot: self.texture_slot,
+            transform,
+            frame_size,
+            // bound by `setup`.
+            blend: (AlphaMode::Premultiplied, BlendMode::AlphaBlend),
+            missing_texture_policy: self.missing_texture_policy,
+            missing_texture: self.missing_texture.as_ref(),
+            warned_missing_textures: &mut self.warned_missing_textures,
+            id_pass: false,
+        };
+        for draw in geometry.draws() {
+            painter.draw(draw)?;
+        }
+        if let Some(id_target) = id_target {
+            unsafe {
+                device_context.OMSetRenderTargets(
+                    Some(&[Some(id_target.clone())]),
+                    depth_stencil.map(|(view, _)| view),
+                );
+                // integer render targets can not be blended.
+                device_context.OMSetBlendState(None, None, u32::MAX);
+            }
+            painter.id_pass = true;
+            for draw in geometry.draws() {
+                painter.draw(draw)?;
+            }
+        }
+        // unbind textures, so that they can be written to afterwards, e.g. by
+        // Direct2D, without conflicting bindings.
+        unsafe {
+            device_context
+                .PSSetShaderResources(self.texture_slot, Some(&[None, None]))
+        };
+        Ok(self.dirty_rect(geometry, transform, frame_size))
+    }
+
+    /// The bounding rectangle of everything drawn from `geometry` in pixels,
+    /// an empty one if nothing is drawn, or `None` if it covers the whole
+    /// frame or is unknown.
+    fn dirty_rect(
+        &self,
+        geometry: &GeometryWriter,
+        transform: OutputTransform,
+        frame_size: (u32, u32),
+    ) -> Option<RECT> {
+        if self.projection_override.is_some() {
+            return None;
+        }
+        let Some(bounds) = geometry
+            .draws()
+            .iter()
+            .map(|draw| draw.bounds.intersect(draw.clip_rect))
+            .filter(|bounds| bounds.is_positive())
+            .reduce(|a, b| a.union(b))
+        else {
+            return Some(RECT::default());
+        };
+        let mut rect = Self::scissor_rect(bounds, transform, frame_size);
+        if self.flip_y {
+            let height = frame_size.1 as i32;
+            (rect.top, rect.bottom) = (height - rect.bottom, height - rect.top);
+        }
+        let full = RECT {
+            left: 0,
+            top: 0,
+            right: frame_size.0 as _,
+            bottom: frame_size.1 as _,
+        };
+        if rect.right <= rect.left || rect.bottom <= rect.top {
+            // everything drawn is outside of the frame.
+            return Some(RECT::default());
+        }
+        (rect != full).then_some(rect)
+    }
+
+    /// Apply the texture updates of an output of `egui` without drawing
+    /// anything. [`Renderer::render`] and its variants do this for you, so
+    /// this is only needed along with [`Renderer::draw_appended`], or to
+    /// upload textures in a separate step before the draw pass, e.g. on the
+    /// immediate context while drawing is recorded on a deferred context.
+    ///
+    /// The updates are applied on `device_context` right away, so geometry
+    /// drawn afterwards by any [`Renderer`] sharing the textures samples the
+    /// updated textures. When uploading ahead like this, take the delta out
+    /// of the output (e.g. with `std::mem::take`) before passing the output
+    /// to [`Renderer::render`], so that it is not applied twice.
+    ///
+    /// `egui` never sends the same update again, so every
+    /// [`egui::TexturesDelta`] must be applied exactly once, before drawing
+    /// any geometry that uses the textures.
+    ///
+    /// Textures freed by `egui` are kept for one more update, as the output
+    /// freeing them may still draw them, e.g. when fonts are reloaded. They
+    /// are released when the next delta is applied, so they do not flicker
+    /// even though the update is applied before drawing.
+    ///
+    /// Likewise, when `egui` replaces the whole image of a texture with one
+    /// of a different size, e.g. when the font atlas grows, the texture is
+    /// recreated under the same id, and the old texture is kept until the next
+    /// delta is applied. Draws always sample the new texture with the options
+    /// of the new image.
+    ///
+    /// ## Several Updates per Frame
+    ///
+    /// The deltas of several outputs may be applied before rendering only the
+    /// last output, e.g. when skipping frames to catch up. They must be
+    /// applied in the order `egui` has produced them, including the delta of
+    /// the rendered output, which is applied by [`Renderer::render`] unless
+    /// taken out. Each delta applies its new and changed images in order,
+    /// then its frees, so later deltas supersede earlier ones: a texture
+    /// created by one delta can be updated partially by the next one and
+    /// freed by a third one. Textures freed by a skipped output are released
+    /// by the next delta, which is safe as only the output freeing a texture
+    /// may still draw it. Note that this includes the geometry recorded for
+    /// [`Renderer::render_cached`], which must not be redrawn after skipped
+    /// deltas have freed its textures. [`Renderer::last_freed_textures`]
+    /// only covers the last delta applied.
+    pub fn update_textures(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        textures_delta: TexturesDelta,
+    ) -> Result<()> {
+        let freed = textures_delta.free.clone();
+        self.lock_texture_pool()
+            .update(device_context, textures_delta)?;
+        self.last_freed_textures = freed;
+        Ok(())
+    }
+
+    /// The ids of the textures freed by the last texture update applied by
+    /// this [`Renderer`], i.e. by [`Renderer::update_textures`],
+    /// [`Renderer::render`] or its variants, in the order `egui` has freed
+    /// them. This is empty if the last update has freed nothing.
+    ///
+    /// This is meant for keeping caches that mirror the textures of `egui`,
+    /// e.g. for streaming them over a network, in sync. Native textures
+    /// removed with [`Renderer::remove_native_texture`] are not included.
+    pub fn last_freed_textures(&self) -> &[TextureId] {
+        &self.last_freed_textures
+    }
+
+    /// Append tessellated `egui` primitives to `writer`, for drawing them
+    /// later with [`Renderer::draw_appended`].
+    ///
+    /// This is a lower-level alternative to [`Renderer::render`], e.g. for
+    /// packing the geometry of several `egui` contexts into one pair of
+    /// buffers and drawing all of it at once. Primitives are usually obtained
+    /// from [`egui::Context::tessellate`]. Their positions are in points and
+    /// are drawn as is, so primitives of different contexts should be laid
+    /// out in the same screen space, and be tessellated with the same pixels
+    /// per point passed to [`Renderer::draw_appended`].
+    pub fn append_geometry(
+        &self,
+        writer: &mut GeometryWriter,
+        primitives: Vec<ClippedPrimitive>,
+    ) {
+        writer.append(&primitives, self.validate);
+    }
+
+    /// Draw all geometry appended to `writer` to the provided
+    /// `render_target`, in the order it has been appended. See
+    /// [`GeometryWriter`] for details.
+    ///
+    /// Unlike [`Renderer::render`], this function does not apply any
+    /// texture updates, so the updates of each `egui` context must be applied
+    /// with [`Renderer::update_textures`] beforehand. The requirements on the
+    /// render target and the pipeline state management are the same as for
+    /// [`Renderer::render`], and [`Renderer::set_preserve_state`] is
+    /// respected.
+    pub fn draw_appended(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        writer: &mut GeometryWriter,
+        pixels_per_point: f32,
+    ) -> Result<()> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.draw_geometry(
+                device_context,
+                render_target,
+                None,
+                writer,
+                pixels_per_point,
+                None,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Draw all geometry appended to `writer` like [`Renderer::draw_appended`],
+    /// and additionally write the draw id of each mesh to `id_buffer`, e.g.
+    /// for picking widgets on the GPU in an editor. Draw ids are set with
+    /// [`GeometryWriter::set_draw_id`] while appending.
+    ///
+    /// `id_buffer` should be a view of a texture of an integer format like
+    /// `DXGI_FORMAT_R32_UINT`, with the same size as `render_target`. The ids
+    /// are drawn in a second pass with blending disabled, wherever a mesh is
+    /// at least half opaque, so that anti-aliased edges and shadows are not
+    /// picked. Later meshes overwrite the ids of earlier ones, like they
+    /// overdraw their colors. The id buffer is never cleared, so clear it to
+    /// a value meaning "nothing" first, e.g. with
+    /// `ID3D11DeviceContext::ClearRenderTargetView`, and read it back by
+    /// copying it to a staging texture.
+    ///
+    /// This function fails if the sizes of `render_target` and `id_buffer`
+    /// differ.
+    pub fn draw_appended_with_id_buffer(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        render_target: &ID3D11RenderTargetView,
+        id_buffer: &ID3D11RenderTargetView,
+        writer: &mut GeometryWriter,
+        pixels_per_point: f32,
+    ) -> Result<()> {
+        self.render_wrapped(device_context, self.preserve_state, |this| {
+            this.draw_geometry(
+                device_context,
+                render_target,
+                Some(id_buffer),
+                writer,
+                pixels_per_point,
+                None,
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Create all pipeline states the [`Renderer`] may need, and draw with
+    /// each shader into a small scratch texture, so that the driver compiles
+    /// them to machine code.
+    ///
+    /// Most of this would otherwise happen during the first frames, causing a
+    /// noticeable hitch, e.g. when an overlay is first shown. Calling this
+    /// function is optional, e.g. during a loading screen, and calling it
+    /// multiple times does no harm. Note that shaders are compiled from HLSL
+    /// in [`Renderer::new`] already, unless the `precompiled` feature is
+    /// enabled. Like [`Renderer::render`], this overrides the pipeline state of
+    /// `device_context` unless [`Renderer::set_preserve_state`] is enabled.
+    pub fn warm_up(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+    ) -> Result<()> {
+        let rasterizer_state = self.rasterizer_state()?;
+        let sampler = self
+            .sampler_cache
+            .get(&SamplerOptions::from(egui::TextureOptions::default()))?;
+        self.sampler_cache.get(&SamplerOptions::default())?;
+        if self.blitter.is_none() {
+            self.blitter = Some(Blitter::new(&self.device)?);
+        }
+
+        let (src, src_srv) =
+            self.create_scratch_target(DXGI_FORMAT_R8G8B8A8_UNORM)?;
+        let (dst, _) =
+            self.create_scratch_target(DXGI_FORMAT_R8G8B8A8_UNORM)?;
+        let (id_target, _) =
+            self.create_scratch_target(DXGI_FORMAT_R32_UINT)?;
+        let mut geometry = GeometryWriter::new();
+        geometry.append(
+            &[ClippedPrimitive {
+                clip_rect: egui::Rect::EVERYTHING,
+                primitive: egui::epaint::Primitive::Mesh(egui::Mesh {
+                    indices: vec![0, 1, 2],
+                    vertices: vec![
+                        egui::epaint::Vertex {
+                            pos: egui::pos2(0., 0.),
+                            uv: egui::Pos2::ZERO,
+                            color: egui::Color32::WHITE,
+                        };
+                        3
+                    ],
+                    texture_id: TextureId::default(),
+                }),
+            }],
+            false,
+        );
+
+        let backup = self
+            .preserve_state
+            .then(|| StateBackup::save(device_context, self.texture_slot));
+        self.screen_size = [1., 1.];
+        self.setup(
+            device_context,
+            &src,
+            &rasterizer_state,
+            OutputTransform::uniform(1., (1, 1)).viewport(1, false),
+        );
+        let result = self
+            .update_constants(
                 device_context,
-                &self.texture_pool,
-                mesh,
+                &Constants {
+                    projection: self.projection_matrix(),
+                    output_encoding: OutputEncoding::Gamma,
+                    opacity: 1.,
+                    vertex_color_space: self.vertex_color_space,
+                    use_color_matrix: 0,
+                    color_matrix: Default::default(),
+                },
+            )
+            .and_then(|()| geometry.bind(&self.device, device_context));
+        if result.is_ok() {
+            unsafe {
+                device_context.RSSetScissorRects(Some(&[RECT {
+                    left: 0,
+                    top: 0,
+                    right: 1,
+                    bottom: 1,
+                }]));
+                device_context.PSSetSamplers(0, Some(&[Some(sampler.clone())]));
+                let [color_shaders @ .., id_shader] = self.pixel_shaders.all();
+                for blend_state in self.blend_states.all() {
+                    device_context.OMSetBlendState(
+                        blend_state,
+                        Some(&[0.; 4]),
+                        u32::MAX,
+                    );
+                    for pixel_shader in color_shaders {
+                        device_context.PSSetShader(pixel_shader, None);
+                        device_context.DrawIndexed(3, 0, 0);
+                    }
+                }
+                // like id passes, see `draw_appended_with_id_buffer`.
+                device_context
+                    .OMSetRenderTargets(Some(&[Some(id_target.clone())]), None);
+                device_context.OMSetBlendState(None, None, u32::MAX);
+                device_context.PSSetShader(id_shader, None);
+                device_context.DrawIndexed(3, 0, 0);
+            }
+            if let Some(blitter) = &self.blitter {
+                blitter.downscale(
+                    device_context,
+                    &sampler,
+                    &src_srv,
+                    &dst,
+                    (1, 1),
+                );
+            }
+        }
+        if let Some(backup) = backup {
+            backup.restore(device_context);
+        }
+        result
+    }
+
+    fn create_scratch_target(
+        &self,
+        format: DXGI_FORMAT,
+    ) -> Result<(ID3D11RenderTargetView, ID3D11ShaderResourceView)> {
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: 1,
+            Height: 1,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: (D3D11_BIND_RENDER_TARGET.0
+                | D3D11_BIND_SHADER_RESOURCE.0) as _,
+            ..Default::default()
+        };
+        let mut tex = None;
+        let mut rtv = None;
+        let mut srv = None;
+        unsafe {
+            self.device.CreateTexture2D(&desc, None, Some(&mut tex))?;
+            let tex = tex.unwrap();
+            self.device
+                .CreateRenderTargetView(&tex, None, Some(&mut rtv))?;
+            self.device
+                .CreateShaderResourceView(&tex, None, Some(&mut srv))?;
+        }
+        Ok((rtv.unwrap(), srv.unwrap()))
+    }
+
+    /// Set whether the scissor test is enabled. Enabled by default.
+    ///
+    /// `egui` clips widgets (e.g. the content of scroll areas and windows)
+    /// with the scissor test. Disabling it saves setting up the scissor rect
+    /// on every draw call, but **breaks clipping of all widgets**. Only
+    /// disable it if none of your widgets need to be clipped, e.g. for simple
+    /// full-screen overlays.
+    pub fn set_scissor(&mut self, enabled: bool) {
+        self.set_rasterizer_desc(D3D11_RASTERIZER_DESC {
+            ScissorEnable: enabled.into(),
+            ..self.rasterizer_desc
+        });
+    }
+
+    /// Set which triangles are culled. Defaults to [`CullMode::None`].
+    ///
+    /// The renderer always binds its own rasterizer state, so the culling
+    /// settings left by the host application never apply to `egui`. `egui`
+    /// does not guarantee a consistent winding order of its triangles, so any
+    /// other mode likely makes parts of the UI invisible. This is only useful
+    /// for custom geometry with a known winding order, e.g. drawn with
+    /// [`Renderer::draw_appended`]. Front faces are clockwise in screen space.
+    pub fn set_cull_mode(&mut self, cull_mode: CullMode) {
+        self.set_rasterizer_desc(D3D11_RASTERIZER_DESC {
+            CullMode: match cull_mode {
+                CullMode::None => D3D11_CULL_NONE,
+                CullMode::Front => D3D11_CULL_FRONT,
+                CullMode::Back => D3D11_CULL_BACK,
+            },
+            ..self.rasterizer_desc
+        });
+    }
+
+    /// Set the constants of paint callbacks, e.g. the time or a camera, which
+    /// are uploaded by each frame and bound to the constant buffer slot
+    /// [`Renderer::CALLBACK_CONSTANT_BUFFER_SLOT`] of the vertex and pixel
+    /// shader stages, so callbacks need not bind their own buffer to a slot
+    /// used by `egui`. The buffer is padded to a multiple of 16 bytes, and is
+    /// only created or grown when the constants are larger than before. An
+    /// empty slice unbinds it again, leaving the slot to the application.
+    ///
+    /// This function fails if `data` is larger than a constant buffer can be,
+    /// i.e. `D3D11_REQ_CONSTANT_BUFFER_ELEMENT_COUNT` times 16 bytes, or if
+    /// the buffer can not be created, in which case the current constants
+    /// are kept.
+    pub fn set_callback_constants(&mut self, data: &[u8]) -> Result<()> {
+        let max_size = D3D11_REQ_CONSTANT_BUFFER_ELEMENT_COUNT as usize * 16;
+        if data.len() > max_size {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "the callback constants are {} bytes, while a constant buffer can only hold {max_size} bytes",
+                    data.len(),
+                ),
+            ));
+        }
+        if data.is_empty() {
+            self.callback_constants.clear();
+            self.callback_constant_buffer = None;
+            return Ok(());
+        }
+        let size = data.len().next_multiple_of(16);
+        let capacity = self.callback_constant_buffer.as_ref().map_or(0, |b| {
+            let mut desc = D3D11_BUFFER_DESC::default();
+            unsafe { b.GetDesc(&mut desc) };
+            desc.ByteWidth as usize
+        });
+        if capacity < size {
+            self.callback_constant_buffer =
+                Some(Self::create_constant_buffer(&self.device, size)?);
+        }
+        self.callback_constants.clear();
+        self.callback_constants.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Bind textures to the shader resource slot `slot` of the pixel shader
+    /// stage instead of slot 0, e.g. to keep resources of the host
+    /// application bound to the lower slots. The chroma plane of video
+    /// textures is bound to the slot after it. Defaults to `0`.
+    ///
+    /// The pixel shaders are compiled again for the new slots, so this
+    /// requires the `precompiled` feature to be disabled for any slot but
+    /// `0`. This function fails if `slot` is out of range, i.e. not below
+    /// `D3D11_COMMONSHADER_INPUT_RESOURCE_SLOT_COUNT - 1`, or if the shaders
+    /// can not be compiled, in which case the current slot is kept.
+    pub fn set_texture_slot(&mut self, slot: u32) -> Result<()> {
+        if slot >= D3D11_COMMONSHADER_INPUT_RESOURCE_SLOT_COUNT - 1 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "the texture slot {slot} is out of range, it must be below {}",
+                    D3D11_COMMONSHADER_INPUT_RESOURCE_SLOT_COUNT - 1,
+                ),
+            ));
+        }
+        if slot != self.texture_slot {
+            self.pixel_shaders = PixelShaders::new(&self.device, slot)?;
+            self.texture_slot = slot;
+        }
+        Ok(())
+    }
+
+    /// Set how the output is combined with the content of the render target.
+    /// Defaults to [`CompositeMode::Blend`].
+    ///
+    /// Overlays drawn over a 3D scene or video, and any UI with transparent
+    /// regions, need [`CompositeMode::Blend`]. [`CompositeMode::Opaque`]
+    /// disables blending, so every pixel covered by a mesh is replaced by its
+    /// color and alpha, and nothing of the background leaks through, even if
+    /// it has been cleared to a translucent color. This is only meant for
+    /// apps where `egui` is the sole content of the target and paints it
+    /// with opaque shapes, e.g. with a [`egui::CentralPanel`]. Note that
+    /// `egui` also blends its own shapes over each other, e.g. the feathered
+    /// edges used for anti-aliasing and the shadows of windows, and these
+    /// replace what is drawn below them in this mode. Disable feathering with
+    /// [`egui::epaint::TessellationOptions::feathering`] and avoid translucent
+    /// fills and shadows for correct results. The blend modes of native
+    /// textures are ignored as well.
+    pub fn set_composite_mode(&mut self, composite_mode: CompositeMode) {
+        self.composite_mode = composite_mode;
+    }
+
+    /// Set how triangles are rasterized. Defaults to [`FillMode::Solid`].
+    ///
+    /// [`FillMode::Wireframe`] is a developer tool for looking at the meshes
+    /// tessellated by `egui`, e.g. to diagnose overdraw or clipping issues.
+    /// The edges are drawn with the colors and textures of the meshes, so
+    /// edges of fully transparent vertices, e.g. of the feathering `egui`
+    /// uses for anti-aliasing, stay invisible. Disable feathering with
+    /// [`egui::epaint::TessellationOptions::feathering`] to see the bare
+    /// triangles.
+    pub fn set_fill_mode(&mut self, fill_mode: FillMode) {
+        self.set_rasterizer_desc(D3D11_RASTERIZER_DESC {
+            FillMode: match fill_mode {
+                FillMode::Solid => D3D11_FILL_SOLID,
+                FillMode::Wireframe => D3D11_FILL_WIREFRAME,
+            },
+            ..self.rasterizer_desc
+        });
+    }
+
+    /// Set whether `egui` is rendered upside down, for render targets whose
+    /// content is stored bottom-up, e.g. textures shared with OpenGL. Disabled
+    /// by default.
+    ///
+    /// This flips the projection matrix (see [`Renderer::projection_matrix`])
+    /// and the scissor rectangles. As flipping reverses the winding order of
+    /// triangles, front faces of [`Renderer::set_cull_mode`] stay clockwise as
+    /// seen in the screen space of `egui`.
+    pub fn set_flip_y(&mut self, flip_y: bool) {
+        self.flip_y = flip_y;
+        self.set_rasterizer_desc(D3D11_RASTERIZER_DESC {
+            FrontCounterClockwise: flip_y.into(),
+            ..self.rasterizer_desc
+        });
+    }
+
+    fn set_rasterizer_desc(&mut self, desc: D3D11_RASTERIZER_DESC) {
+        if self.rasterizer_desc != desc {
+            self.rasterizer_desc = desc;
+            self.rasterizer_state = None;
+        }
+    }
+
+    fn rasterizer_state(&mut self) -> Result<ID3D11RasterizerState> {
+        if let Some(rasterizer_state) = &self.rasterizer_state {
+            return Ok(rasterizer_state.clone());
+        }
+        let rasterizer_state =
+            Self::create_rasterizer_state(&self.device, &self.rasterizer_desc)?;
+        self.rasterizer_state = Some(rasterizer_state.clone());
+        Ok(rasterizer_state)
+    }
+
+    /// Set whether the GPU time spent by [`Renderer::render`] and its variants
+    /// is measured. Disabled by default, as the timestamp queries involved
+    /// add a small overhead. See [`Renderer::last_gpu_time_ms`] for details.
+    /// Frames rendered on deferred contexts are not measured.
+    ///
+    /// This function fails if the queries can not be created.
+    pub fn enable_gpu_timing(&mut self, enabled: bool) -> Result<()> {
+        if !enabled {
+            self.gpu_timer = None;
+        } else if self.gpu_timer.is_none() {
+            self.gpu_timer = Some(GpuTimer::new(&self.device)?);
+        }
+        Ok(())
+    }
+
+    /// The GPU time spent by a recent call to [`Renderer::render`] or its
+    /// variants in milliseconds, including texture updates.
+    ///
+    /// Results of GPU queries become available a few frames after the
+    /// frame is rendered, and are read back without stalling. So the returned
+    /// value usually lags two or three frames behind, and is `None` until the
+    /// first result is available or if GPU timing is disabled with
+    /// [`Renderer::enable_gpu_timing`]. Frames during which the GPU clock
+    /// changes (e.g. due to power management) are not measured, and neither
+    /// are frames whose queries fail, which is logged as a warning instead of
+    /// failing the frame.
+    pub fn last_gpu_time_ms(&self) -> Option<f32> {
+        self.gpu_timer.as_ref()?.last_time_ms()
+    }
+
+    /// Set whether the CPU time spent by [`Renderer::render`] and its variants
+    /// is measured. Disabled by default. See [`Renderer::last_cpu_time_us`]
+    /// for details.
+    pub fn enable_cpu_timing(&mut self, enabled: bool) {
+        self.cpu_timing = enabled;
+        if !enabled {
+            self.last_cpu_time_us = None;
+        }
+    }
+
+    /// The wall-clock time spent on the CPU by the last call to
+    /// [`Renderer::render`] or its variants in microseconds, i.e. for
+    /// uploading textures and geometry, setting up the pipeline state and
+    /// submitting draw calls.
+    ///
+    /// Comparing this with [`Renderer::last_gpu_time_ms`] tells whether
+    /// rendering `egui` is CPU- or GPU-bound. Note that the driver may defer
+    /// work to the GPU or to other threads, so this does not include the
+    /// time until the commands are executed. Returns `None` until the first
+    /// frame is measured, or if CPU timing is disabled with
+    /// [`Renderer::enable_cpu_timing`].
+    pub fn last_cpu_time_us(&self) -> Option<u64> {
+        self.last_cpu_time_us
+    }
+
+    /// Whether the device of the [`Renderer`] supports `feature`, so that
+    /// applications can fall back gracefully instead of failing to create a
+    /// resource later. This is a thin wrapper over
+    /// `ID3D11Device::CheckFormatSupport`,
+    /// `ID3D11Device::CheckMultisampleQualityLevels` and the DXGI queries noted
+    /// for each [`Feature`].
+    ///
+    /// [`Feature::Hdr10Output`] checks the displays of the adapter with
+    /// `IDXGIOutput6::GetDesc1`, whose color space follows the HDR setting of
+    /// Windows, so the result may change while the application runs, e.g.
+    /// on `WM_DISPLAYCHANGE`. Failed queries count as unsupported.
+    pub fn check_support(&self, feature: Feature) -> bool {
+        let format_support = |format, flags: &[D3D11_FORMAT_SUPPORT]| {
+            let support = unsafe { self.device.CheckFormatSupport(format) }
+                .unwrap_or_default();
+            flags.iter().all(|flag| support & flag.0 as u32 != 0)
+        };
+        match feature {
+            Feature::Msaa { format, samples } => {
+                format_support(
+                    format,
+                    &[D3D11_FORMAT_SUPPORT_MULTISAMPLE_RENDERTARGET],
+                ) && unsafe {
+                    self.device.CheckMultisampleQualityLevels(format, samples)
+                }
+                .is_ok_and(|levels| levels > 0)
+            },
+            Feature::GenerateMips(format) => {
+                format_support(format, &[D3D11_FORMAT_SUPPORT_MIP_AUTOGEN])
+            },
+            Feature::Hdr10Output => {
+                format_support(
+                    DXGI_FORMAT_R10G10B10A2_UNORM,
+                    &[
+                        D3D11_FORMAT_SUPPORT_RENDER_TARGET,
+                        D3D11_FORMAT_SUPPORT_BLENDABLE,
+                    ],
+                ) && self.has_hdr10_output().unwrap_or(false)
+            },
+            Feature::ScRgbOutput => format_support(
+                DXGI_FORMAT_R16G16B16A16_FLOAT,
+                &[
+                    D3D11_FORMAT_SUPPORT_RENDER_TARGET,
+                    D3D11_FORMAT_SUPPORT_BLENDABLE,
+                ],
+            ),
+            Feature::Tearing => self.check_tearing_support().unwrap_or(false),
+        }
+    }
+
+    /// Whether a display of the adapter is in HDR10 mode.
+    fn has_hdr10_output(&self) -> Result<bool> {
+        let adapter =
+            unsafe { self.device.cast::<IDXGIDevice>()?.GetAdapter() }?;
+        for i in 0.. {
+            let Ok(output) = (unsafe { adapter.EnumOutputs(i) }) else {
+                break;
+            };
+            let desc = unsafe { output.cast::<IDXGIOutput6>()?.GetDesc1() }?;
+            if desc.ColorSpace == DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020 {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn check_tearing_support(&self) -> Result<bool> {
+        let adapter =
+            unsafe { self.device.cast::<IDXGIDevice>()?.GetAdapter() }?;
+        let factory = unsafe { adapter.GetParent::<IDXGIFactory5>() }?;
+        let mut allow_tearing = BOOL(0);
+        unsafe {
+            factory.CheckFeatureSupport(
+                DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+                &mut allow_tearing as *mut _ as _,
+                mem::size_of::<BOOL>() as _,
+            )
+        }?;
+        Ok(allow_tearing.as_bool())
+    }
+
+    /// Set the color space of the render target, e.g. as reported for the
+    /// monitor showing the window by `IDXGIOutput6::GetDesc1`. Colors of
+    /// `egui`, which are in sRGB, are converted to this color space when being
+    /// written to the render target.
+    ///
+    /// The supported color spaces are:
+    /// + `DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709` (the default), i.e. sRGB.
+    ///   Colors are written as is, unless the render target has an `_SRGB`
+    ///   format, see [`Renderer::render`] for details;
+    /// + `DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709`, i.e. scRGB, typically
+    ///   with a `DXGI_FORMAT_R16G16B16A16_FLOAT` render target. Colors are
+    ///   written in linear space;
+    /// + `DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020`, i.e. HDR10, typically
+    ///   with a `DXGI_FORMAT_R10G10B10A2_UNORM` render target. Colors are
+    ///   converted to Rec. 2020 primaries and encoded with the PQ curve.
+    ///
+    /// For HDR color spaces, white of `egui` is mapped to 80 nits, the
+    /// reference white of scRGB. Note that blending happens in the encoding
+    /// of the render target, so translucent areas differ from what `egui`
+    /// intends, most noticeably with HDR10. The swap chain should be set to
+    /// the same color space with `IDXGISwapChain3::SetColorSpace1`.
+    ///
+    /// This function fails if `color_space` is not supported, in which case
+    /// the current color space is kept.
+    pub fn set_output_color_space(
+        &mut self,
+        color_space: DXGI_COLOR_SPACE_TYPE,
+    ) -> Result<()> {
+        match color_space {
+            DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709
+            | DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709
+            | DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020 => {
+                self.output_color_space = color_space;
+                Ok(())
+            },
+            _ => Err(Error::new(
+                E_INVALIDARG,
+                format!("unsupported output color space {}", color_space.0),
+            )),
+        }
+    }
+
+    fn output_encoding(
+        &self,
+        render_target: &ID3D11RenderTargetView,
+    ) -> OutputEncoding {
+        match self.output_color_space {
+            DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709 => OutputEncoding::Linear,
+            DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020 => OutputEncoding::Pq,
+            _ if Self::is_srgb_render_target(render_target) => {
+                OutputEncoding::Linear
+            },
+            _ => OutputEncoding::Gamma,
+        }
+    }
+
+    /// Set the opacity of everything rendered by the [`Renderer`], e.g. for
+    /// fading an overlay in and out. The value is clamped to `0.0..=1.0`, and
+    /// defaults to `1.0`, i.e. fully opaque.
+    ///
+    /// All colors output by the pixel shader, which are premultiplied, are
+    /// multiplied by the opacity, so the result is the same as blending the
+    /// whole layer over the render target with the specified opacity, except
+    /// where widgets overlap.
+    pub fn set_layer_opacity(&mut self, opacity: f32) {
+        self.layer_opacity = opacity.clamp(0., 1.);
+    }
+
+    /// Transform all colors output by the [`Renderer`] with a 4x4 matrix, e.g.
+    /// for accessibility filters like grayscale or the simulation of color
+    /// blindness. See [`ColorMatrix`] for presets. Pass `None` to disable the
+    /// transformation, which is the default.
+    ///
+    /// The matrix is in row-major order, and is applied to column vectors
+    /// `(r, g, b, a)` of unmultiplied colors in linear space, after sampling
+    /// textures and before the colors are encoded for the render target and
+    /// multiplied by the layer opacity. The resulting alpha is clamped to
+    /// `0.0..=1.0`. Additive colors with zero alpha are not transformed.
+    pub fn set_color_matrix(&mut self, matrix: Option<[[f32; 4]; 4]>) {
+        self.color_matrix = matrix;
+    }
+
+    /// Set the color space of the vertex colors of the rendered geometry.
+    /// Defaults to [`VertexColorSpace::Gamma`], which is what `egui` produces.
+    ///
+    /// With [`VertexColorSpace::Linear`], vertex colors are converted to gamma
+    /// space in the vertex shader, so that they are blended in gamma space
+    /// along with textures, as `egui` expects. This is for engines that
+    /// convert all colors to linear space before handing them to the
+    /// renderer. Vertex colors are still expected to be premultiplied.
+    pub fn set_vertex_color_space(&mut self, color_space: VertexColorSpace) {
+        self.vertex_color_space = color_space;
+    }
+
+    /// The size of the screen in points used by the last call to
+    /// [`Renderer::render`] or its variants, i.e. the size of the render
+    /// target divided by the scale factor.
+    ///
+    /// Returns `[0.0, 0.0]` if nothing has been rendered yet.
+    pub fn current_screen_size(&self) -> [f32; 2] {
+        self.screen_size
+    }
+
+    /// The orthographic projection matrix used by the last call to
+    /// [`Renderer::render`] or its variants, in row-major order.
+    ///
+    /// The matrix is applied to column vectors `(x, y, 0, 1)`, where `x` and
+    /// `y` are positions in points as in [`egui::epaint::Vertex::pos`], and
+    /// yields positions in normalized device coordinates. Custom shaders
+    /// drawing into `egui` regions should transform their vertices the same
+    /// way for them to line up with `egui`. The top left corner of the screen
+    /// is mapped to `(-1, 1)` and the bottom right corner to `(1, -1)`, or to
+    /// `(-1, -1)` and `(1, 1)` with [`Renderer::set_flip_y`] enabled. If a
+    /// matrix has been set with [`Renderer::set_projection_override`], that
+    /// matrix is returned instead.
+    ///
+    /// The renderer uploads this matrix, followed by other data, to the
+    /// beginning of the constant buffer bound to slot `b0` of both the vertex
+    /// and the pixel shader stages, declared in HLSL as
+    /// `row_major float4x4 projection;`.
+    pub fn projection_matrix(&self) -> [[f32; 4]; 4] {
+        if let Some(projection) = self.projection_override {
+            return projection;
+        }
+        let [width, height] = self.screen_size;
+        let flip = if self.flip_y { -1. } else { 1. };
+        [
+            [2. / width, 0., 0., -1.],
+            [0., -2. / height * flip, 0., flip],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ]
+    }
+
+    /// Replace the orthographic projection matrix computed from the screen size
+    /// (see [`Renderer::projection_matrix`]) with the provided matrix, e.g. to
+    /// pre-transform `egui` for placing it on a surface in a 3D scene. Pass
+    /// `None` to go back to the computed matrix, which is the default.
+    ///
+    /// The matrix is in row-major order, and is applied to column vectors
+    /// `(x, y, 0, 1)` with positions in points. It must yield clip space
+    /// positions, which are divided by their `w` component into normalized
+    /// device coordinates, where `(-1, 1)` is the top left and `(1, -1)` the
+    /// bottom right corner of the render target. Note that scissor rectangles
+    /// are still computed from the clip rectangles of `egui` in screen space,
+    /// so disable the scissor test with [`Renderer::set_scissor`] if the
+    /// matrix moves `egui` around.
+    pub fn set_projection_override(
+        &mut self,
+        projection: Option<[[f32; 4]; 4]>,
+    ) {
+        self.projection_override = projection;
+    }
+
+    /// Render the output of `egui` to the provided `texture`.
+    ///
+    /// This is a convenience wrapper around [`Renderer::render`] that creates
+    /// the render target view for you, and returns the dirty rectangle the
+    /// same way. Render target views are cached by the texture they are
+    /// created for, so repeatedly rendering to the same texture does not
+    /// create a new view every frame. The same requirements on the color space
+    /// of the render target as [`Renderer::render`] apply.
+    ///
+    /// Note that cached views hold a reference to their textures. Call
+    /// [`Renderer::clear_render_target_cache`] if you need the textures to be
+    /// released immediately.
+    pub fn render_to_texture(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        texture: &ID3D11Texture2D,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        let render_target = self
+            .render_target_cache
+            .get_or_create(&self.device, texture)?;
+        self.render(device_context, &render_target, egui_ctx, egui_output)
+    }
+
+    /// Render the output of `egui` to one subresource of the provided
+    /// `texture`, i.e. to the mip level `mip` of the array slice
+    /// `array_slice`, e.g. to one face of a cube map, or to one layer of a
+    /// texture array for layered VR rendering.
+    ///
+    /// This behaves like [`Renderer::render_to_texture`], with the view
+    /// restricted to the subresource. The screen size of `egui_ctx` should
+    /// match the size of the mip level. Use `0` for both indices to render to
+    /// the first slice of an array texture. Multisampled textures only have
+    /// one mip level.
+    ///
+    /// This function fails if `mip` or `array_slice` is out of range for
+    /// `texture`.
+    pub fn render_to_subresource(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        texture: &ID3D11Texture2D,
+        mip: u32,
+        array_slice: u32,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        let render_target =
+            self.render_target_cache.get_or_create_subresource(
+                &self.device,
+                texture,
+                Some((mip, array_slice)),
             )?;
+        self.render(device_context, &render_target, egui_ctx, egui_output)
+    }
+
+    /// Render the output of `egui` to the provided `texture` at `scale` times
+    /// the resolution of [`RendererOutput::pixels_per_point`], e.g. for
+    /// supersampling. The texture should be `scale` times as large as the
+    /// screen of `egui_ctx` in pixels.
+    ///
+    /// A typical supersampling setup renders at a `scale` of `2.0` to an
+    /// offscreen texture, and downscales it to the back buffer with
+    /// [`Renderer::blit_downscale`]. Note that this costs a texture of four
+    /// times the size of the back buffer in VRAM (e.g. 32 MiB for a 1080p
+    /// screen in `DXGI_FORMAT_R8G8B8A8_UNORM`), four times the fill rate, and
+    /// a full-screen pass for downscaling.
+    ///
+    /// Only geometry is tessellated at the higher resolution. `egui`
+    /// rasterizes text at its own pixels per point, so for text to benefit,
+    /// scale the pixels per point of `egui` itself instead (e.g. with
+    /// [`egui::ViewportInfo::native_pixels_per_point`] in the raw input) and
+    /// use [`Renderer::render_to_texture`].
+    pub fn render_to_texture_scaled(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        texture: &ID3D11Texture2D,
+        egui_ctx: &egui::Context,
+        mut egui_output: RendererOutput,
+        scale: f32,
+    ) -> Result<Option<RECT>> {
+        egui_output.pixels_per_point *= scale;
+        self.render_to_texture(device_context, texture, egui_ctx, egui_output)
+    }
+
+    /// Render the output of `egui` to the provided `texture`, scaling the
+    /// logical canvas of `logical_size` points to the size of the texture,
+    /// e.g. to render a UI laid out for 1920x1080 points into a 1280x720
+    /// texture for streaming. `logical_size` is usually the size of
+    /// [`egui::Context::screen_rect`], and defines the projection, while the
+    /// texture defines the viewport.
+    ///
+    /// Unlike the pixels per point of `egui`, which handle DPI scaling and
+    /// change the layout, this is a pure resize of the output. With
+    /// [`FitMode::Stretch`] the canvas covers the whole texture, and is
+    /// distorted if its aspect ratio differs from the texture. With
+    /// [`FitMode::Letterbox`] it keeps its aspect ratio and is centered, and
+    /// the remaining bars at the sides are left as they are, so clear the
+    /// texture first.
+    ///
+    /// Geometry is tessellated for the larger of the two scales. Text is
+    /// rasterized by `egui` at its own pixels per point, and is resampled
+    /// like other textures. The dirty rectangle and
+    /// [`Renderer::current_screen_size`] refer to the scaled canvas, and the
+    /// size mismatch warning of [`Renderer::render`] is not logged.
+    ///
+    /// This function fails if `logical_size` is not positive.
+    pub fn render_to_texture_fit(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        texture: &ID3D11Texture2D,
+        egui_ctx: &egui::Context,
+        mut egui_output: RendererOutput,
+        logical_size: Vec2,
+        fit: FitMode,
+    ) -> Result<Option<RECT>> {
+        if !(logical_size.x > 0. && logical_size.y > 0.) {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "the logical size must be positive",
+            ));
+        }
+        let render_target = self
+            .render_target_cache
+            .get_or_create(&self.device, texture)?;
+        let frame_size = Self::get_render_target_size(&render_target)?;
+        let transform = OutputTransform::fit(logical_size, frame_size, fit);
+        egui_output.pixels_per_point = transform.scale.max_elem();
+        self.output_transform = Some(transform);
+        let result =
+            self.render(device_context, &render_target, egui_ctx, egui_output);
+        self.output_transform = None;
+        result
+    }
+
+    /// Downscale the content of `src` to fit `dst` with a box filter, e.g.
+    /// for supersampling with [`Renderer::render_to_texture_scaled`].
+    ///
+    /// The filter is exact for downscaling by 2x and 4x, and an approximation
+    /// for other ratios. Colors are averaged as stored, i.e. in gamma space
+    /// unless `src` has an `_SRGB` format. The previous content of `dst` is
+    /// overwritten without blending. The shaders used are created on first
+    /// call.
+    ///
+    /// Like [`Renderer::render`], this overrides the pipeline state of
+    /// `device_context` unless [`Renderer::set_preserve_state`] is enabled.
+    /// Particularly, it overrides the input layout and primitive topology,
+    /// the vertex shader, the viewport and rasterizer state, the pixel shader,
+    /// shader resource slot 0 and sampler slot 0, and the render target(s) and
+    /// blend state.
+    pub fn blit_downscale(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        src: &ID3D11ShaderResourceView,
+        dst: &ID3D11RenderTargetView,
+    ) -> Result<()> {
+        let dst_size = Self::get_render_target_size(dst)?;
+        let sampler = self.sampler_cache.get(&SamplerOptions {
+            magnification: egui::TextureFilter::Linear,
+            minification: egui::TextureFilter::Linear,
+            address_mode: AddressMode::Clamp,
+            ..SamplerOptions::default()
+        })?;
+        let blitter = match &mut self.blitter {
+            Some(blitter) => blitter,
+            blitter => blitter.insert(Blitter::new(&self.device)?),
+        };
+
+        let backup = self
+            .preserve_state
+            .then(|| StateBackup::save(device_context, 0));
+        blitter.downscale(device_context, &sampler, src, dst, dst_size);
+        if let Some(backup) = backup {
+            backup.restore(device_context);
         }
         Ok(())
     }
 
-    /// Register a Texture2D for use in egui
-    pub fn register_native_texture(
+    /// Render the output of `egui` to the back buffer of the provided
+    /// `swap_chain`.
+    ///
+    /// This is a convenience wrapper around [`Renderer::render_to_texture`]
+    /// using buffer 0 of the swap chain as the render target.
+    ///
+    /// **Resize the swap chain with [`Renderer::resize_swap_chain`], or call
+    /// [`Renderer::clear_render_target_cache`] before calling
+    /// `IDXGISwapChain::ResizeBuffers`**, as the cached render target view
+    /// holds a reference to the back buffer and `ResizeBuffers` fails with
+    /// `DXGI_ERROR_INVALID_CALL` if any such reference is alive.
+    ///
+    /// ## Flip Model
+    ///
+    /// Swap chains using the flip model (`DXGI_SWAP_EFFECT_FLIP_DISCARD` or
+    /// `DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL`) are supported as well as those
+    /// using the legacy `DXGI_SWAP_EFFECT_DISCARD` of the examples. Unlike
+    /// Direct3D12, Direct3D11 always exposes the current back buffer as
+    /// buffer 0, so its view can be cached across `IDXGISwapChain::Present`.
+    /// Note that under the flip model, `Present` unbinds the back buffer from
+    /// the device context, which is why this function binds it every frame.
+    /// Your own rendering to the back buffer must bind it every frame too, or
+    /// every frame but the first ends up black.
+    pub fn render_to_swap_chain(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        swap_chain: &IDXGISwapChain,
+        egui_ctx: &egui::Context,
+        egui_output: RendererOutput,
+    ) -> Result<Option<RECT>> {
+        let back_buffer =
+            unsafe { swap_chain.GetBuffer::<ID3D11Texture2D>(0) }?;
+        self.render_to_texture(
+            device_context,
+            &back_buffer,
+            egui_ctx,
+            egui_output,
+        )
+    }
+
+    /// Resize the buffers of `swap_chain` to `width` by `height` pixels of
+    /// `format`, keeping their count and the flags of the swap chain, e.g. in
+    /// response to `WM_SIZE`. Pass `0` for `width` and `height` to use the
+    /// size of the window, and `DXGI_FORMAT_UNKNOWN` to keep the format.
+    ///
+    /// This releases the render target views cached by
+    /// [`Renderer::render_to_swap_chain`] first, which would otherwise make
+    /// `IDXGISwapChain::ResizeBuffers` fail. Views of the back buffer created
+    /// by yourself must be released before calling this function as well.
+    pub fn resize_swap_chain(
+        &mut self,
+        swap_chain: &IDXGISwapChain,
+        width: u32,
+        height: u32,
+        format: DXGI_FORMAT,
+    ) -> Result<()> {
+        self.clear_render_target_cache();
+        let desc = unsafe { swap_chain.GetDesc() }?;
+        unsafe {
+            swap_chain.ResizeBuffers(
+                0,
+                width,
+                height,
+                format,
+                DXGI_SWAP_CHAIN_FLAG(desc.Flags as _),
+            )
+        }
+    }
+
+    /// Present the back buffer of `swap_chain`, waiting for `sync_interval`
+    /// vertical blanks (between 0 and 4) and allowing tearing if requested.
+    ///
+    /// Tearing requires a `sync_interval` of 0, a swap chain created with
+    /// `DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING` (which requires the flip model and
+    /// system support, see `IDXGIFactory5::CheckFeatureSupport`), and the
+    /// swap chain not to be in exclusive fullscreen mode. This function fails
+    /// without presenting if `allow_tearing` is requested while any of these
+    /// does not hold, or if `sync_interval` is out of range, instead of having
+    /// `IDXGISwapChain::Present` fail with `DXGI_ERROR_INVALID_CALL`. Use
+    /// `SwapChainConfig::present` with the `setup` feature to fall back to
+    /// presenting without tearing instead.
+    ///
+    /// Errors returned by `IDXGISwapChain::Present` itself, e.g. when the
+    /// device has been removed, are passed on. Note that `DXGI_STATUS_OCCLUDED`
+    /// is a success code, so it is not reported.
+    pub fn present(
+        swap_chain: &IDXGISwapChain,
+        sync_interval: u32,
+        allow_tearing: bool,
+    ) -> Result<()> {
+        if sync_interval > 4 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!("sync interval {sync_interval} is not between 0 and 4"),
+            ));
+        }
+        let flags = if allow_tearing {
+            if sync_interval != 0 {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    format!(
+                        "tearing requires a sync interval of 0, got {sync_interval}"
+                    ),
+                ));
+            }
+            let desc = unsafe { swap_chain.GetDesc() }?;
+            if desc.Flags & DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32 == 0 {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    "tearing requires a swap chain created with DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING",
+                ));
+            }
+            let mut fullscreen = BOOL(0);
+            unsafe {
+                swap_chain.GetFullscreenState(Some(&mut fullscreen), None)
+            }?;
+            if fullscreen.as_bool() {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    "tearing is not allowed in exclusive fullscreen mode",
+                ));
+            }
+            DXGI_PRESENT_ALLOW_TEARING
+        } else {
+            DXGI_PRESENT(0)
+        };
+        unsafe { swap_chain.Present(sync_interval, flags) }.ok()
+    }
+
+    /// Block until the GPU has completed all commands submitted to
+    /// `device_context` so far, e.g. to read back a frame rendered with
+    /// [`Renderer::render_to_texture`] right away.
+    ///
+    /// This stalls the CPU until the GPU is idle, which costs the overlap
+    /// between CPU and GPU work for the frame and usually takes milliseconds.
+    /// It is meant for synchronous capture, e.g. screenshots and tests, and
+    /// should not be called in the render loop. Note that mapping a staging
+    /// texture without `D3D11_MAP_FLAG_DO_NOT_WAIT` already waits for the copy
+    /// into it, so a flush is only needed when the GPU has to be idle.
+    ///
+    /// `device_context` must be an immediate context, as queries can not be
+    /// read back from deferred contexts.
+    pub fn flush(&self, device_context: &ID3D11DeviceContext) -> Result<()> {
+        if unsafe { device_context.GetType() } != D3D11_DEVICE_CONTEXT_IMMEDIATE
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "only immediate device contexts can be flushed",
+            ));
+        }
+        let mut query = None;
+        unsafe {
+            self.device.CreateQuery(
+                &D3D11_QUERY_DESC {
+                    Query: D3D11_QUERY_EVENT,
+                    MiscFlags: 0,
+                },
+                Some(&mut query),
+            )
+        }?;
+        let query = query.unwrap();
+        unsafe {
+            device_context.End(&query);
+            device_context.Flush();
+        }
+        while get_query_data::<BOOL>(device_context, &query)?.is_none() {
+            std::thread::yield_now();
+        }
+        Ok(())
+    }
+
+    /// Release all render target views cached by
+    /// [`Renderer::render_to_texture`] and [`Renderer::render_to_swap_chain`],
+    /// along with the references they hold to their textures.
+    pub fn clear_render_target_cache(&mut self) {
+        self.render_target_cache.clear();
+    }
+
+    /// The maximum width and height of textures created by the [`Renderer`].
+    ///
+    /// This defaults to the limit of the feature level of the device, e.g.
+    /// 16384 pixels on feature level 11. You should pass this value to `egui`
+    /// (via [`egui::RawInput::max_texture_side`], or the `max_texture_side`
+    /// argument of `egui_winit::State::new`) so that `egui` never requests a
+    /// larger texture, e.g. for its font atlas.
+    ///
+    /// If `egui` requests a larger texture anyway, [`Renderer::render`] fails
+    /// with an error naming the requested size.
+    pub fn max_texture_dimension(&self) -> usize {
+        self.lock_texture_pool().max_texture_dimension()
+    }
+
+    /// Lower the maximum width and height of textures created by the
+    /// [`Renderer`]. See [`Renderer::max_texture_dimension`] for details.
+    ///
+    /// The limit of the feature level of the device can not be exceeded, and
+    /// larger values are clamped to it.
+    pub fn set_max_texture_dimension(&mut self, dimension: usize) {
+        self.lock_texture_pool()
+            .set_max_texture_dimension(dimension);
+    }
+
+    /// Set the sampler options of native textures registered from now on
+    /// without explicit options, e.g. with [`Renderer::register_native_texture`]
+    /// or [`Renderer::register_rgba8`], to set a sampling policy for the
+    /// whole application, such as anisotropic filtering. Defaults to
+    /// [`SamplerOptions::default`].
+    ///
+    /// Options passed when registering a texture, e.g. with
+    /// [`Renderer::register_native_texture_ex`], and set later with
+    /// [`Renderer::set_texture_options`] take precedence. Textures managed
+    /// by `egui` are sampled as `egui` requests. Like the maximum texture
+    /// dimension, the defaults are shared by [`Renderer`]s sharing the texture
+    /// pool, while each of them keeps its own cache of sampler states.
+    ///
+    /// The sampler state is created right away in the cache of this
+    /// [`Renderer`], so this function fails if it can not be created, in which
+    /// case the current defaults are kept. Other [`Renderer`]s sharing the
+    /// pool create it lazily in their own cache, on their first draw with a
+    /// texture using it.
+    pub fn set_default_sampler_options(
+        &mut self,
+        options: SamplerOptions,
+    ) -> Result<()> {
+        self.sampler_cache.get(&options)?;
+        self.lock_texture_pool().set_default_sampler(options);
+        Ok(())
+    }
+
+    /// Register a Texture2D for use in egui
+    ///
+    /// Registering a texture that is already registered (i.e. the same COM
+    /// object) does not create a new shader resource view, and returns the id
+    /// of the existing registration instead.
+    ///
+    /// ## Texture Formats
+    ///
+    /// A shader resource view of the whole texture is created with the format
+    /// of the texture. Any format that can be sampled as `float4` works,
+    /// including block-compressed formats like `DXGI_FORMAT_BC7_UNORM` and
+    /// `DXGI_FORMAT_BC3_UNORM`. Sampled colors are used as-is, so they should
+    /// be premultiplied and in gamma space as `egui` expects. Particularly,
+    /// avoid `_SRGB` formats, which are decoded to linear space when sampled.
+    ///
+    /// If you need a different view of the texture, e.g. for a specific mip
+    /// range or a different format, use
+    /// [`Renderer::register_native_texture_with_srv`]. For textures with a
+    /// `_TYPELESS` format, use
+    /// [`Renderer::register_native_texture_with_format`]. For planar video
+    /// formats like `DXGI_FORMAT_NV12`, use
+    /// [`Renderer::register_video_texture`].
+    ///
+    /// If the view can not be created and the texture has a `_TYPELESS`
+    /// format, a view is created with a typed format of the same family
+    /// instead, e.g. `DXGI_FORMAT_R8G8B8A8_UNORM` for
+    /// `DXGI_FORMAT_R8G8B8A8_TYPELESS`. This function fails if no view can be
+    /// created.
+    pub fn register_native_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+    ) -> Result<TextureId> {
+        let options = self.lock_texture_pool().default_native_options();
+        self.register_native_texture_ex(texture, options)
+    }
+
+    /// Register a Texture2D for use in egui, with the provided options on
+    /// how to draw with it. See [`NativeTextureOptions`] for details.
+    ///
+    /// If the texture is already registered, its options are replaced and the
+    /// id of the existing registration is returned. Views are created as for
+    /// [`Renderer::register_native_texture`].
+    pub fn register_native_texture_ex(
+        &mut self,
+        texture: ID3D11Texture2D,
+        options: NativeTextureOptions,
+    ) -> Result<TextureId> {
+        self.lock_texture_pool()
+            .register_native_texture(texture, options)
+    }
+
+    /// Register a Texture2D for use in egui, drawing with the provided shader
+    /// resource view of it instead of one created by the [`Renderer`].
+    ///
+    /// The view must be a `Texture2D` view. This function fails if the view is
+    /// not a view of a Texture2D. If the texture is already registered, its
+    /// view and options are replaced and the id of the existing registration
+    /// is returned.
+    pub fn register_native_texture_with_srv(
+        &mut self,
+        srv: ID3D11ShaderResourceView,
+        options: NativeTextureOptions,
+    ) -> Result<TextureId> {
+        let texture =
+            unsafe { srv.GetResource() }?.cast::<ID3D11Texture2D>()?;
+        Ok(self
+            .lock_texture_pool()
+            .register_native_texture_with_srv(texture, srv, options))
+    }
+
+    /// Copy a staging texture into a new texture on the GPU and register it for
+    /// use in egui, e.g. for frames captured to a staging texture, without
+    /// reading its pixels back to the CPU.
+    ///
+    /// The new texture has the size, format and mip levels of `staging`, and
+    /// is registered with the default [`NativeTextureOptions`]. Use
+    /// [`Renderer::set_texture_options`] to change how it is sampled. The
+    /// copy is recorded on `device_context`, so `staging` may be reused
+    /// right away, and must not be mapped at that point. Later changes to
+    /// `staging` are not reflected in the registered texture.
+    ///
+    /// This function fails if `staging` is not a staging texture, is a texture
+    /// array, exceeds [`Renderer::max_texture_dimension`], or has a format
+    /// that can not be sampled.
+    pub fn register_from_staging(
+        &mut self,
+        device_context: &ID3D11DeviceContext,
+        staging: &ID3D11Texture2D,
+    ) -> Result<TextureId> {
+        self.lock_texture_pool()
+            .register_from_staging(device_context, staging)
+    }
+
+    /// Create a texture from RGBA8 pixels and register it for use in egui,
+    /// e.g. for images decoded from files.
+    ///
+    /// `rgba` holds `width * height` pixels in rows from top to bottom,
+    /// without any padding between rows, and with 4 bytes per pixel in the
+    /// order red, green, blue and alpha. Colors should not be premultiplied by
+    /// alpha, as in most image files, and are premultiplied by this function
+    /// as `egui` expects. The texture is immutable, and is released when
+    /// removed with [`Renderer::remove_native_texture`].
+    ///
+    /// This function fails if `rgba` does not have the expected length, or
+    /// if the size is empty or exceeds [`Renderer::max_texture_dimension`].
+    pub fn register_rgba8(
+        &mut self,
+        width: usize,
+        height: usize,
+        rgba: &[u8],
+    ) -> Result<TextureId> {
+        self.lock_texture_pool().register_rgba8(width, height, rgba)
+    }
+
+    /// Register a Texture2D for use in egui, drawing with a view of it in the
+    /// provided `format` instead of the format of the texture.
+    ///
+    /// This is mostly useful for textures created with a `_TYPELESS` format,
+    /// e.g. shared surfaces opened with `ID3D11Device::OpenSharedResource`,
+    /// for which a view can not be created without specifying a typed format.
+    /// Pass a typed format of the same family, like
+    /// `DXGI_FORMAT_R8G8B8A8_UNORM` for `DXGI_FORMAT_R8G8B8A8_TYPELESS`. The
+    /// view covers all mip levels of the texture.
+    ///
+    /// This function fails if `format` is incompatible with the format of the
+    /// texture. If the texture is already registered, its view and options
+    /// are replaced and the id of the existing registration is returned.
+    pub fn register_native_texture_with_format(
+        &mut self,
+        texture: ID3D11Texture2D,
+        format: DXGI_FORMAT,
+        options: NativeTextureOptions,
+    ) -> Result<TextureId> {
+        self.lock_texture_pool()
+            .register_native_texture_with_format(texture, format, options)
+    }
+
+    /// Register a video texture, e.g. a surface decoded by Media Foundation,
+    /// for use in egui. Colors are converted to RGB in the pixel shader,
+    /// see [`VideoFormat`] for the supported formats and their conversions.
+    ///
+    /// The texture must be created with `D3D11_BIND_SHADER_RESOURCE` and an
+    /// `ArraySize` of 1. Decoders usually output to texture arrays, whose
+    /// slices should be copied to such a texture first, e.g. with
+    /// `ID3D11DeviceContext::CopySubresourceRegion`. This function fails if
+    /// the format of the texture does not match `format`, or if the views of
+    /// its planes can not be created.
+    ///
+    /// As with [`Renderer::register_native_texture`], registering a texture
+    /// that is already registered returns the id of the existing registration.
+    pub fn register_video_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+        format: VideoFormat,
+    ) -> Result<TextureId> {
+        self.lock_texture_pool()
+            .register_video_texture(texture, format)
+    }
+
+    /// Register a single-channel coverage mask, e.g. of a monochrome icon or a
+    /// signed distance field rendered to coverage, for use in egui. The red
+    /// channel of the texture is sampled as coverage, which multiplies the
+    /// vertex color, so the same mask may be drawn in any color, e.g. with
+    /// [`egui::Image::tint`]. This takes a quarter of the memory of an RGBA
+    /// texture.
+    ///
+    /// The texture must be created with `D3D11_BIND_SHADER_RESOURCE` and have
+    /// a single channel format, i.e. `DXGI_FORMAT_R8_UNORM`,
+    /// `DXGI_FORMAT_R16_UNORM`, `DXGI_FORMAT_R16_FLOAT` or
+    /// `DXGI_FORMAT_R32_FLOAT`, or the corresponding `_TYPELESS` format. This
+    /// function fails for any other format. Note that `DXGI_FORMAT_A8_UNORM`
+    /// is not supported, as its coverage is not stored in the red channel.
+    ///
+    /// As with [`Renderer::register_native_texture`], registering a texture
+    /// that is already registered returns the id of the existing registration.
+    /// [`Renderer::update_native_texture`] keeps drawing the texture as a mask.
+    pub fn register_mask_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+    ) -> Result<TextureId> {
+        self.lock_texture_pool().register_mask_texture(texture)
+    }
+
+    /// Set how a registered native texture is sampled, overriding the sampler
+    /// options it has been registered with. This may be called at any time,
+    /// e.g. for toggling nearest filtering in a pixel inspector, and takes
+    /// effect on the next draw. Mipmap options are ignored, as for textures
+    /// managed by `egui`. The LOD and anisotropy settings of the texture (see
+    /// [`SamplerOptions`]) are kept, as `egui` has no such options.
+    ///
+    /// This function fails if `tid` is not a registered native texture. The
+    /// options of textures managed by `egui` are set by its texture updates.
+    pub fn set_texture_options(
+        &mut self,
+        tid: TextureId,
+        options: egui::TextureOptions,
+    ) -> Result<()> {
+        self.lock_texture_pool()
+            .set_native_texture_options(tid, options)
+    }
+
+    /// Set how a registered native texture is blended with the render target,
+    /// overriding the blend mode it has been registered with, e.g. for
+    /// textures registered with [`Renderer::register_mask_texture`]. This
+    /// takes effect on the next draw.
+    ///
+    /// Consecutive draws with the same blend and alpha modes share the blend
+    /// state, so mixing modes in a frame costs a state change whenever they
+    /// change. This function fails if `tid` is not a registered native
+    /// texture, as textures managed by `egui` are always alpha blended.
+    pub fn set_texture_blend_mode(
+        &mut self,
+        tid: TextureId,
+        blend_mode: BlendMode,
+    ) -> Result<()> {
+        self.lock_texture_pool()
+            .set_native_texture_blend_mode(tid, blend_mode)
+    }
+
+    /// Draw a registered native texture with the provided sampler state, e.g.
+    /// a sampler with a specific border color or LOD bias, instead of the
+    /// sampler created from its sampler options. Pass `None` to go back to the
+    /// sampler options.
+    ///
+    /// The sampler is bound to sampler slot 0 whenever the texture is drawn,
+    /// even with [`Renderer::set_manage_states`] disabled. Note that the pixel
+    /// shaders of the renderer do not sample with comparison, so comparison
+    /// samplers have no effect. The sampler is kept when the texture is
+    /// replaced with [`Renderer::update_native_texture`], but not when it is
+    /// registered again.
+    ///
+    /// This function fails if `tid` is not a registered native texture, or if
+    /// `sampler` has been created on a different device.
+    pub fn set_texture_sampler(
+        &mut self,
+        tid: TextureId,
+        sampler: Option<ID3D11SamplerState>,
+    ) -> Result<()> {
+        self.lock_texture_pool()
+            .set_native_texture_sampler(tid, sampler)
+    }
+
+    /// Replace the texture of a registered native texture, keeping its id and
+    /// options. This is meant for textures that are recreated from time to
+    /// time, e.g. with a different size for a live-resizing canvas or video,
+    /// so that widgets referring to the id keep working.
+    ///
+    /// A new shader resource view is created for `texture`: video textures
+    /// are viewed as the same [`VideoFormat`], textures registered with a
+    /// specific view format keep that format, and other textures are viewed
+    /// with their own format. Note that `egui` knows nothing about the size of
+    /// native textures, so you need to update the size of widgets showing the
+    /// texture (e.g. of [`egui::Image`]) yourself.
+    ///
+    /// This function fails if `tid` is not a registered native texture, or if
+    /// the view can not be created.
+    pub fn update_native_texture(
         &mut self,
+        tid: TextureId,
         texture: ID3D11Texture2D,
-    ) -> TextureId {
-        self.texture_pool.register_native_texture(texture)
+    ) -> Result<()> {
+        self.lock_texture_pool().update_native_texture(tid, texture)
     }
 
     /// Remove the texture from use in egui, likely to delete it entirely
@@ -307,83 +3461,294 @@ impl Renderer {
         &mut self,
         tid: &TextureId,
     ) -> Option<ID3D11Texture2D> {
-        self.texture_pool.remove_native_texture(tid)
+        self.lock_texture_pool().remove_native_texture(tid)
     }
 
-    fn setup(
+    /// Whether `tid` refers to a texture known to the [`Renderer`], i.e. a
+    /// texture managed by `egui` that has not been freed yet, or a native
+    /// texture that has not been removed.
+    ///
+    /// Drawing with an unknown texture is handled as configured with
+    /// [`Renderer::set_missing_texture_policy`], so this may be used to skip
+    /// images whose texture is gone.
+    pub fn contains_texture(&self, tid: TextureId) -> bool {
+        self.lock_texture_pool().contains(tid)
+    }
+
+    /// The ids of all native textures currently registered with the
+    /// [`Renderer`], in the order they were first registered.
+    ///
+    /// The ids are collected when this function is called, so registering or
+    /// removing textures afterwards does not affect the returned iterator.
+    pub fn native_texture_ids(&self) -> impl Iterator<Item = TextureId> {
+        self.lock_texture_pool().native_texture_ids().into_iter()
+    }
+
+    /// Remove all native textures registered with
+    /// [`Renderer::register_native_texture`], releasing the textures and the
+    /// shader resource views held by the [`Renderer`]. Returns the number of
+    /// removed textures.
+    ///
+    /// Textures managed by `egui` are not affected.
+    pub fn clear_native_textures(&mut self) -> usize {
+        self.lock_texture_pool().clear_native_textures()
+    }
+
+    /// Remove the native textures for which `f` returns `false`, like
+    /// [`std::collections::HashMap::retain`], and return the number of
+    /// removed textures. Their textures and shader resource views are
+    /// released by the [`Renderer`].
+    ///
+    /// `f` is called once for the id of each native texture, in no particular
+    /// order. Textures managed by `egui` are not affected.
+    pub fn retain_native_textures(
+        &mut self,
+        f: impl FnMut(TextureId) -> bool,
+    ) -> usize {
+        self.lock_texture_pool().retain_native_textures(f)
+    }
+
+    /// Install an observer receiving events about the lifecycle of textures,
+    /// or remove it with `None`. No observer is installed by default, which
+    /// costs nothing.
+    ///
+    /// The observer belongs to the pool of textures, so it also receives the
+    /// events of [`Renderer`]s sharing the pool (see [`SharedTexturePool`]),
+    /// and replaces any observer installed by them.
+    pub fn set_texture_observer(
         &mut self,
+        observer: Option<Box<dyn TextureObserver>>,
+    ) {
+        self.lock_texture_pool().set_observer(observer);
+    }
+
+    /// Keep a copy of the pixels of every texture managed by `egui` in RAM,
+    /// as required by [`Renderer::reupload_managed_textures`] and
+    /// [`Renderer::export_texture_cache`]. Disabled by default.
+    ///
+    /// `egui` never sends the content of a texture again, so the copies are
+    /// the only way to restore textures quickly, e.g. after their content was
+    /// lost, without restarting `egui`. They cost as much RAM as the textures
+    /// cost VRAM, e.g. 16 MiB for a 2048x2048 font atlas. Without copies,
+    /// textures are updated in place with `UpdateSubresource`.
+    ///
+    /// Enabling only affects textures created afterwards, so enable it
+    /// before the first frame. Disabling drops the copies of all textures,
+    /// recreating them without one, and fails if a texture can not be
+    /// recreated. Like the maximum texture dimension, this is shared by
+    /// [`Renderer`]s sharing the texture pool.
+    pub fn set_keep_cpu_mirror(&mut self, keep: bool) -> Result<()> {
+        self.lock_texture_pool().set_keep_cpu_mirror(keep)
+    }
+
+    /// Whether copies of the pixels of managed textures are kept, see
+    /// [`Renderer::set_keep_cpu_mirror`].
+    pub fn keep_cpu_mirror(&self) -> bool {
+        self.lock_texture_pool().keep_cpu_mirror()
+    }
+
+    /// Recreate all textures managed by `egui` from the copies of their pixels
+    /// kept by the [`Renderer`], e.g. when the content of a texture is
+    /// suspected to be corrupted.
+    ///
+    /// `egui` never sends the content of a texture again, so this is the only
+    /// way to restore it without restarting `egui`. The ids of the textures
+    /// stay the same. Native textures are not affected.
+    ///
+    /// This requires [`Renderer::set_keep_cpu_mirror`] to have been enabled
+    /// before the textures were created, and fails otherwise without changing
+    /// any texture.
+    pub fn reupload_managed_textures(&mut self) -> Result<()> {
+        self.lock_texture_pool().reupload_managed_textures()
+    }
+
+    /// Serialize the textures managed by `egui` to an opaque blob, from the
+    /// copies of their pixels kept by the [`Renderer`], along with their ids
+    /// and sampler options. Textures freed by the last update are excluded.
+    ///
+    /// Together with [`Renderer::import_texture_cache`], this preserves the
+    /// textures of an `egui` context across a reload of the application, e.g.
+    /// for hot-reloading, as `egui` never sends the content of a texture
+    /// again. The blob is only meant to be imported by the same version of
+    /// this crate.
+    ///
+    /// Native textures are not included, as the [`Renderer`] does not own
+    /// their content. They have to be registered again after a reload, and
+    /// may get different ids. Neither are managed textures created while
+    /// [`Renderer::set_keep_cpu_mirror`] was disabled, which is logged as a
+    /// warning.
+    pub fn export_texture_cache(&self) -> Vec<u8> {
+        self.lock_texture_pool().export_managed_textures()
+    }
+
+    /// Recreate the textures managed by `egui` from a blob returned by
+    /// [`Renderer::export_texture_cache`], possibly of a [`Renderer`] on
+    /// another device. Returns the number of imported textures, which does
+    /// not include empty textures skipped in the blob.
+    ///
+    /// Textures keep the ids they were exported with, replacing existing
+    /// textures of the same ids, which are kept until the next texture update
+    /// like freed textures, as draws in flight may still use them. This
+    /// function fails without changing any
+    /// texture if the blob is malformed, and may fail after importing some
+    /// textures if a texture can not be created.
+    pub fn import_texture_cache(&mut self, blob: &[u8]) -> Result<usize> {
+        self.lock_texture_pool().import_managed_textures(blob)
+    }
+
+    fn lock_texture_pool(&self) -> MutexGuard<'_, TexturePool> {
+        // the pool stays consistent even if a panic occurs while it is locked.
+        self.texture_pool
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn setup(
+        &self,
         ctx: &ID3D11DeviceContext,
         render_target: &ID3D11RenderTargetView,
-        frame_size: (u32, u32),
+        rasterizer_state: &ID3D11RasterizerState,
+        viewport: D3D11_VIEWPORT,
     ) {
         unsafe {
             ctx.IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
             ctx.IASetInputLayout(&self.input_layout);
             ctx.VSSetShader(&self.vertex_shader, None);
-            ctx.PSSetShader(&self.pixel_shader, None);
-            ctx.RSSetState(&self.rasterizer_state);
-            ctx.RSSetViewports(Some(&[D3D11_VIEWPORT {
-                TopLeftX: 0.,
-                TopLeftY: 0.,
-                Width: frame_size.0 as _,
-                Height: frame_size.1 as _,
-                MinDepth: 0.,
-                MaxDepth: 1.,
-            }]));
-            ctx.PSSetSamplers(0, Some(&[Some(self.sampler_state.clone())]));
+            if self.manage_states {
+                ctx.RSSetState(rasterizer_state);
+            }
+            ctx.RSSetViewports(Some(&[viewport]));
+            ctx.VSSetConstantBuffers(
+                0,
+                Some(&[Some(self.constant_buffer.clone())]),
+            );
+            ctx.PSSetConstantBuffers(
+                0,
+                Some(&[
+                    Some(self.constant_buffer.clone()),
+                    Some(self.draw_constant_buffer.clone()),
+                ]),
+            );
+            if let Some(buffer) = &self.callback_constant_buffer {
+                let slot = Self::CALLBACK_CONSTANT_BUFFER_SLOT;
+                ctx.VSSetConstantBuffers(slot, Some(&[Some(buffer.clone())]));
+                ctx.PSSetConstantBuffers(slot, Some(&[Some(buffer.clone())]));
+            }
             ctx.OMSetRenderTargets(Some(&[Some(render_target.clone())]), None);
-            ctx.OMSetBlendState(&self.blend_state, Some(&[0.; 4]), u32::MAX);
+            if self.manage_states {
+                ctx.OMSetDepthStencilState(&self.depth_states.depth, 0);
+                ctx.OMSetBlendState(
+                    self.blend_states.get(
+                        self.composite_mode,
+                        AlphaMode::Premultiplied,
+                        BlendMode::AlphaBlend,
+                    ),
+                    Some(&[0.; 4]),
+                    u32::MAX,
+                );
+            }
         }
     }
 
-    fn draw_mesh(
-        device: &ID3D11Device,
-        device_context: &ID3D11DeviceContext,
-        texture_pool: &TexturePool,
-        mesh: MeshData,
-    ) -> Result<()> {
-        let vb = Self::create_index_buffer(device, &mesh.idx)?;
-        let ib = Self::create_vertex_buffer(device, &mesh.vtx)?;
-        unsafe {
-            device_context.IASetVertexBuffers(
-                0,
-                1,
-                Some(&Some(ib)),
-                Some(&(mem::size_of::<VertexData>() as _)),
-                Some(&0),
-            );
-            device_context.IASetIndexBuffer(&vb, DXGI_FORMAT_R32_UINT, 0);
-            device_context.RSSetScissorRects(Some(&[RECT {
-                left: mesh.clip_rect.left() as _,
-                top: mesh.clip_rect.top() as _,
-                right: mesh.clip_rect.right() as _,
-                bottom: mesh.clip_rect.bottom() as _,
-            }]));
-        }
-        if let Some(srv) = texture_pool.get_srv(mesh.tex) {
-            unsafe {
-                device_context.PSSetShaderResources(0, Some(&[Some(srv)]))
-            };
-        } else {
-            println!("A");
+    fn check_frame_size(&mut self, frame_size: (u32, u32), screen_size: Vec2) {
+        let mismatched = (screen_size.x - frame_size.0 as f32).abs() > 1.
+            || (screen_size.y - frame_size.1 as f32).abs() > 1.;
+        if !mismatched {
+            self.mismatched_frame_size = None;
+        } else if self.mismatched_frame_size != Some(frame_size) {
+            // warn only once per size, as this may persist for a few frames
+            // during resizing.
+            self.mismatched_frame_size = Some(frame_size);
             log::warn!(
                 concat!(
-                    "egui wants to sample a non-existing texture {:?}.",
-                    "this request will be ignored."
+                    "the render target is {}x{} pixels, while egui expects ",
+                    "{}x{} pixels. the render target may be stale, e.g. it has ",
+                    "not been recreated after the window was resized."
                 ),
-                mesh.tex
+                frame_size.0,
+                frame_size.1,
+                screen_size.x.round(),
+                screen_size.y.round(),
             );
-        };
-        unsafe { device_context.DrawIndexed(mesh.idx.len() as _, 0, 0) };
+        }
+    }
+
+    /// Convert a clip rect in points to a scissor rect in pixels. The rect is
+    /// rounded outward, so that partially covered pixels at the edges are not
+    /// clipped, and clamped to the render target.
+    fn scissor_rect(
+        clip_rect: egui::Rect,
+        transform: OutputTransform,
+        frame_size: (u32, u32),
+    ) -> RECT {
+        let OutputTransform { scale, offset, .. } = transform;
+        let min = (clip_rect.min.to_vec2() * scale + offset).floor();
+        let max = (clip_rect.max.to_vec2() * scale + offset).ceil();
+        let (width, height) = (frame_size.0 as f32, frame_size.1 as f32);
+        RECT {
+            left: min.x.clamp(0., width) as _,
+            top: min.y.clamp(0., height) as _,
+            right: max.x.clamp(0., width) as _,
+            bottom: max.y.clamp(0., height) as _,
+        }
+    }
+
+    fn update_constants(
+        &self,
+        ctx: &ID3D11DeviceContext,
+        constants: &Constants,
+    ) -> Result<()> {
+        Self::update_constant_buffer(ctx, &self.constant_buffer, constants)?;
+        Self::update_constant_buffer(
+            ctx,
+            &self.draw_constant_buffer,
+            &DrawConstants::default(),
+        )?;
+        if let Some(buffer) = &self.callback_constant_buffer {
+            unsafe {
+                let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+                ctx.Map(
+                    buffer,
+                    0,
+                    D3D11_MAP_WRITE_DISCARD,
+                    0,
+                    Some(&mut mapped),
+                )?;
+                (mapped.pData as *mut u8).copy_from_nonoverlapping(
+                    self.callback_constants.as_ptr(),
+                    self.callback_constants.len(),
+                );
+                ctx.Unmap(buffer, 0);
+            }
+        }
+        Ok(())
+    }
+
+    fn update_constant_buffer<T>(
+        ctx: &ID3D11DeviceContext,
+        buffer: &ID3D11Buffer,
+        data: &T,
+    ) -> Result<()> {
+        unsafe {
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            ctx.Map(buffer, 0, D3D11_MAP_WRITE_DISCARD, 0, Some(&mut mapped))?;
+            (mapped.pData as *mut T).copy_from_nonoverlapping(data, 1);
+            ctx.Unmap(buffer, 0);
+        }
         Ok(())
     }
 }
 
 impl Renderer {
-    const VS_BLOB: &'static [u8] = include_bytes!("../shaders/vs_egui.bin");
-    const PS_BLOB: &'static [u8] = include_bytes!("../shaders/ps_egui.bin");
+    /// The constant buffer slot reserved for the constants of paint callbacks,
+    /// see [`Renderer::set_callback_constants`]. The slots below it are used
+    /// by the shaders of `egui`, i.e. `b0` and `b1`.
+    pub const CALLBACK_CONSTANT_BUFFER_SLOT: u32 = 2;
 
-    const INPUT_ELEMENTS_DESC: [D3D11_INPUT_ELEMENT_DESC; 3] = [
+    /// The input layout of the vertex shader of the [`Renderer`], for custom
+    /// geometry drawn with the same layout. See [`VERTEX_STRIDE`] for the
+    /// memory layout of a vertex.
+    pub const INPUT_ELEMENTS_DESC: [D3D11_INPUT_ELEMENT_DESC; 3] = [
         D3D11_INPUT_ELEMENT_DESC {
             SemanticName: windows::core::s!("POSITION"),
             SemanticIndex: 0,
@@ -426,16 +3791,6 @@ impl Renderer {
         AntialiasedLineEnable: BOOL(0),
     };
 
-    const SAMPLER_DESC: D3D11_SAMPLER_DESC = D3D11_SAMPLER_DESC {
-        Filter: D3D11_FILTER_MIN_MAG_MIP_LINEAR,
-        AddressU: D3D11_TEXTURE_ADDRESS_BORDER,
-        AddressV: D3D11_TEXTURE_ADDRESS_BORDER,
-        AddressW: D3D11_TEXTURE_ADDRESS_BORDER,
-        ComparisonFunc: D3D11_COMPARISON_ALWAYS,
-        BorderColor: [1., 1., 1., 1.],
-        ..zeroed()
-    };
-
     const BLEND_DESC: D3D11_BLEND_DESC = D3D11_BLEND_DESC {
         RenderTarget: [
             D3D11_RENDER_TARGET_BLEND_DESC {
@@ -461,58 +3816,825 @@ impl Renderer {
 }
 
 impl Renderer {
-    fn create_vertex_buffer(
+    fn create_missing_texture(
         device: &ID3D11Device,
-        data: &[VertexData],
-    ) -> Result<ID3D11Buffer> {
-        let mut vertex_buffer = None;
+    ) -> Result<ID3D11ShaderResourceView> {
+        let magenta = [255u8, 0, 255, 255];
+        let mut tex = None;
         unsafe {
-            device.CreateBuffer(
-                &D3D11_BUFFER_DESC {
-                    ByteWidth: mem::size_of_val(data) as _,
+            device.CreateTexture2D(
+                &D3D11_TEXTURE2D_DESC {
+                    Width: 1,
+                    Height: 1,
+                    MipLevels: 1,
+                    ArraySize: 1,
+                    Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
                     Usage: D3D11_USAGE_IMMUTABLE,
-                    BindFlags: D3D11_BIND_VERTEX_BUFFER.0 as _,
-                    ..D3D11_BUFFER_DESC::default()
+                    BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
+                    ..Default::default()
                 },
                 Some(&D3D11_SUBRESOURCE_DATA {
-                    pSysMem: data.as_ptr() as _,
-                    ..D3D11_SUBRESOURCE_DATA::default()
+                    pSysMem: magenta.as_ptr() as _,
+                    SysMemPitch: magenta.len() as _,
+                    SysMemSlicePitch: 0,
                 }),
-                Some(&mut vertex_buffer),
+                Some(&mut tex),
             )
         }?;
-        Ok(vertex_buffer.unwrap())
+        let mut srv = None;
+        unsafe {
+            device.CreateShaderResourceView(&tex.unwrap(), None, Some(&mut srv))
+        }?;
+        Ok(srv.unwrap())
+    }
+
+    fn create_rasterizer_state(
+        device: &ID3D11Device,
+        desc: &D3D11_RASTERIZER_DESC,
+    ) -> Result<ID3D11RasterizerState> {
+        let mut rasterizer_state = None;
+        unsafe {
+            device.CreateRasterizerState(desc, Some(&mut rasterizer_state))
+        }?;
+        Ok(rasterizer_state.unwrap())
     }
 
-    fn create_index_buffer(
+    fn create_constant_buffer(
         device: &ID3D11Device,
-        data: &[u32],
+        size: usize,
     ) -> Result<ID3D11Buffer> {
-        let mut index_buffer = None;
+        let mut constant_buffer = None;
         unsafe {
             device.CreateBuffer(
                 &D3D11_BUFFER_DESC {
-                    ByteWidth: mem::size_of_val(data) as _,
-                    Usage: D3D11_USAGE_IMMUTABLE,
-                    BindFlags: D3D11_BIND_INDEX_BUFFER.0 as _,
+                    ByteWidth: size as _,
+                    Usage: D3D11_USAGE_DYNAMIC,
+                    BindFlags: D3D11_BIND_CONSTANT_BUFFER.0 as _,
+                    CPUAccessFlags: D3D11_CPU_ACCESS_WRITE.0 as _,
                     ..D3D11_BUFFER_DESC::default()
                 },
-                Some(&D3D11_SUBRESOURCE_DATA {
-                    pSysMem: data.as_ptr() as _,
-                    ..D3D11_SUBRESOURCE_DATA::default()
-                }),
-                Some(&mut index_buffer),
+                None,
+                Some(&mut constant_buffer),
             )
         }?;
-        Ok(index_buffer.unwrap())
+        Ok(constant_buffer.unwrap())
+    }
+
+    fn is_srgb_render_target(rtv: &ID3D11RenderTargetView) -> bool {
+        let mut desc = zeroed();
+        unsafe { rtv.GetDesc(&mut desc) };
+        matches!(
+            desc.Format,
+            DXGI_FORMAT_R8G8B8A8_UNORM_SRGB
+                | DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+                | DXGI_FORMAT_B8G8R8X8_UNORM_SRGB
+        )
     }
 
     fn get_render_target_size(
         rtv: &ID3D11RenderTargetView,
     ) -> Result<(u32, u32)> {
-        let tex = unsafe { rtv.GetResource() }?.cast::<ID3D11Texture2D>()?;
-        let mut desc = zeroed();
-        unsafe { tex.GetDesc(&mut desc) };
-        Ok((desc.Width, desc.Height))
+        let tex = unsafe { rtv.GetResource() }
+            .and_then(|res| res.cast::<ID3D11Texture2D>())
+            .map_err(|err| {
+                Error::new(
+                    err.code(),
+                    "the render target view is not a view of a live Texture2D",
+                )
+            })?;
+        let mut desc = zeroed::<D3D11_TEXTURE2D_DESC>();
+        let mut view_desc = zeroed::<D3D11_RENDER_TARGET_VIEW_DESC>();
+        unsafe {
+            tex.GetDesc(&mut desc);
+            rtv.GetDesc(&mut view_desc);
+        }
+        // the view may target a smaller mip level of the texture.
+        let mip = unsafe {
+            match view_desc.ViewDimension {
+                D3D11_RTV_DIMENSION_TEXTURE2D => {
+                    view_desc.Anonymous.Texture2D.MipSlice
+                },
+                D3D11_RTV_DIMENSION_TEXTURE2DARRAY => {
+                    view_desc.Anonymous.Texture2DArray.MipSlice
+                },
+                _ => 0,
+            }
+        };
+        Ok(((desc.Width >> mip).max(1), (desc.Height >> mip).max(1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scissor_rects_round_outward() {
+        let clip_rect = egui::Rect::from_min_max(
+            egui::pos2(0.3, 0.7),
+            egui::pos2(10.2, 10.5),
+        );
+        let transform = OutputTransform::uniform(1.5, (100, 100));
+        let rect = Renderer::scissor_rect(clip_rect, transform, (100, 100));
+        // 0.45, 1.05, 15.3 and 15.75 pixels.
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (0, 1, 16, 16)
+        );
+    }
+
+    #[test]
+    fn scissor_rects_are_clamped_to_the_frame() {
+        let transform = OutputTransform::uniform(1., (100, 50));
+        let rect = Renderer::scissor_rect(
+            egui::Rect::EVERYTHING,
+            transform,
+            (100, 50),
+        );
+        assert_eq!(
+            (rect.left, rect.top, rect.right, rect.bottom),
+            (0, 0, 100, 50)
+        );
+        let outside = egui::Rect::from_min_max(
+            egui::pos2(200., 10.),
+            egui::pos2(300., 20.),
+        );
+        let rect = Renderer::scissor_rect(outside, transform, (100, 50));
+        assert_eq!(rect.left, rect.right);
+    }
+
+    #[cfg(feature = "test-util")]
+    const SIZE: u32 = 64;
+
+    #[cfg(feature = "test-util")]
+    fn render_target(
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+    ) -> (ID3D11Texture2D, ID3D11RenderTargetView) {
+        render_target_with_format(device, ctx, DXGI_FORMAT_R8G8B8A8_UNORM)
+    }
+
+    #[cfg(feature = "test-util")]
+    fn render_target_with_format(
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+        format: DXGI_FORMAT,
+    ) -> (ID3D11Texture2D, ID3D11RenderTargetView) {
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: SIZE,
+            Height: SIZE,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_RENDER_TARGET.0 as _,
+            ..Default::default()
+        };
+        let mut tex = None;
+        let mut rtv = None;
+        unsafe {
+            device.CreateTexture2D(&desc, None, Some(&mut tex)).unwrap();
+            device
+                .CreateRenderTargetView(
+                    tex.as_ref().unwrap(),
+                    None,
+                    Some(&mut rtv),
+                )
+                .unwrap();
+            ctx.ClearRenderTargetView(rtv.as_ref().unwrap(), &[0.; 4]);
+        }
+        (tex.unwrap(), rtv.unwrap())
+    }
+
+    #[cfg(feature = "test-util")]
+    fn run(
+        egui_ctx: &egui::Context,
+        paint: impl FnMut(&egui::Context),
+    ) -> RendererOutput {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(SIZE as _, SIZE as _),
+            )),
+            ..Default::default()
+        };
+        split_output(egui_ctx.run(input, paint)).renderer_output
+    }
+
+    #[cfg(feature = "test-util")]
+    fn paint_rect(egui_ctx: &egui::Context) {
+        egui_ctx
+            .layer_painter(egui::LayerId::background())
+            .rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(16., 16.),
+                    egui::pos2(48., 48.),
+                ),
+                0.,
+                egui::Color32::RED,
+            );
+    }
+
+    #[cfg(feature = "test-util")]
+    fn pixel(
+        ctx: &ID3D11DeviceContext,
+        tex: &ID3D11Texture2D,
+        [x, y]: [usize; 2],
+    ) -> egui::Color32 {
+        texture::tests::read_back(ctx, tex)[y * SIZE as usize + x]
+    }
+
+    /// A texture of `format` for sampling, with rows of `pitch` bytes of
+    /// `data`, where a row of a block-compressed format is a row of blocks.
+    #[cfg(feature = "test-util")]
+    fn native_texture(
+        device: &ID3D11Device,
+        format: DXGI_FORMAT,
+        [width, height]: [u32; 2],
+        data: &[u8],
+        pitch: usize,
+    ) -> ID3D11Texture2D {
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: width,
+            Height: height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
+            ..Default::default()
+        };
+        let data = D3D11_SUBRESOURCE_DATA {
+            pSysMem: data.as_ptr() as _,
+            SysMemPitch: pitch as _,
+            SysMemSlicePitch: 0,
+        };
+        let mut tex = None;
+        unsafe { device.CreateTexture2D(&desc, Some(&data), Some(&mut tex)) }
+            .unwrap();
+        tex.unwrap()
+    }
+
+    /// Paint the whole texture `tid` over `rect`, multiplied by `tint`.
+    #[cfg(feature = "test-util")]
+    fn paint_image(
+        egui_ctx: &egui::Context,
+        tid: TextureId,
+        rect: egui::Rect,
+        tint: egui::Color32,
+    ) {
+        egui_ctx.layer_painter(egui::LayerId::background()).image(
+            tid,
+            rect,
+            egui::Rect::from_min_max(egui::Pos2::ZERO, egui::pos2(1., 1.)),
+            tint,
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    fn assert_near(color: egui::Color32, expected: egui::Color32) {
+        for (channel, expected_channel) in
+            color.to_array().into_iter().zip(expected.to_array())
+        {
+            assert!(
+                channel.abs_diff(expected_channel) <= 1,
+                "{color:?} is not {expected:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn render_draws_shapes_within_the_dirty_rect() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, paint_rect);
+        let rect = renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+
+        assert_eq!(pixel(&ctx, &tex, [32, 32]), egui::Color32::RED);
+        assert_eq!(pixel(&ctx, &tex, [4, 4]), egui::Color32::TRANSPARENT);
+        let rect = rect.expect("only part of the frame is drawn");
+        assert!(rect != RECT::default());
+        assert!(rect.left <= 16 && rect.top <= 16);
+        assert!(rect.right >= 48 && rect.bottom >= 48);
+        assert!(rect.left > 0 && rect.right < SIZE as i32);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn srgb_targets_store_the_same_gray_as_unorm_targets() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let gray = egui::Color32::from_gray(128);
+        let [unorm, srgb] =
+            [DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB].map(
+                |format| {
+                    let (tex, rtv) =
+                        render_target_with_format(&device, &ctx, format);
+                    let egui_ctx = egui::Context::default();
+                    let output = run(&egui_ctx, |egui_ctx| {
+                        egui_ctx
+                            .layer_painter(egui::LayerId::background())
+                            .rect_filled(egui::Rect::EVERYTHING, 0., gray);
+                    });
+                    renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+                    pixel(&ctx, &tex, [32, 32])
+                },
+            );
+        assert_eq!(unorm, gray);
+        // the sRGB target encodes the linear output of the shader in
+        // hardware, so it holds the same gamma-space value up to rounding,
+        // instead of a doubly encoded one.
+        for (channel, expected) in
+            srgb.to_array().into_iter().zip(gray.to_array())
+        {
+            assert!(channel.abs_diff(expected) <= 1, "{srgb:?}");
+        }
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn block_compressed_native_textures_are_sampled() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        // a block of opaque red each. the BC3 block has a constant alpha of
+        // 255, and equal 5:6:5 colors of red. the BC7 block is of mode 6
+        // with equal endpoints of 7 bits, with their lowest bits set to 1.
+        let bc3 = [255, 255, 0, 0, 0, 0, 0, 0, 0, 0xf8, 0, 0xf8, 0, 0, 0, 0];
+        let bc7 = ((1u128 << 6)
+            | (127 << 7)
+            | (127 << 14)
+            | (127 << 49)
+            | (127 << 56)
+            | (1 << 63)
+            | (1 << 64))
+            .to_le_bytes();
+        let rect = egui::Rect::from_min_max(
+            egui::pos2(16., 16.),
+            egui::pos2(48., 48.),
+        );
+        for (format, block, expected) in [
+            (DXGI_FORMAT_BC3_UNORM, bc3, egui::Color32::RED),
+            (
+                DXGI_FORMAT_BC7_UNORM,
+                bc7,
+                egui::Color32::from_rgb(255, 1, 1),
+            ),
+        ] {
+            let texture = native_texture(&device, format, [4, 4], &block, 16);
+            let tid = renderer.register_native_texture(texture).unwrap();
+            let (tex, rtv) = render_target(&device, &ctx);
+            let egui_ctx = egui::Context::default();
+            let output = run(&egui_ctx, |egui_ctx| {
+                paint_image(egui_ctx, tid, rect, egui::Color32::WHITE);
+            });
+            renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+            assert_near(pixel(&ctx, &tex, [32, 32]), expected);
+            assert_eq!(pixel(&ctx, &tex, [4, 4]), egui::Color32::TRANSPARENT);
+        }
+    }
+
+    /// A black and a white texel side by side.
+    #[cfg(feature = "test-util")]
+    fn black_and_white() -> egui::ColorImage {
+        egui::ColorImage::new(
+            [2, 1],
+            vec![egui::Color32::BLACK, egui::Color32::WHITE],
+        )
+    }
+
+    /// Render `tid` over the whole frame with the sampler selected for it,
+    /// returning a pixel between the centers of its texels. It is black with
+    /// nearest filtering, and dark gray with linear filtering.
+    #[cfg(feature = "test-util")]
+    fn render_filtered(
+        renderer: &mut Renderer,
+        ctx: &ID3D11DeviceContext,
+        egui_ctx: &egui::Context,
+        tid: TextureId,
+    ) -> egui::Color32 {
+        let device = unsafe { ctx.GetDevice() }.unwrap();
+        let (tex, rtv) = render_target(&device, ctx);
+        let output = run(egui_ctx, |egui_ctx| {
+            paint_image(
+                egui_ctx,
+                tid,
+                egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(SIZE as _, SIZE as _),
+                ),
+                egui::Color32::WHITE,
+            );
+        });
+        renderer.render(ctx, &rtv, egui_ctx, output).unwrap();
+        pixel(ctx, &tex, [24, 32])
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn managed_texture_filter_changes_between_frames() {
+        let (_, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let egui_ctx = egui::Context::default();
+        let mut handle = egui_ctx.load_texture(
+            "black and white",
+            black_and_white(),
+            egui::TextureOptions::NEAREST,
+        );
+        let nearest =
+            render_filtered(&mut renderer, &ctx, &egui_ctx, handle.id());
+        assert_eq!(nearest, egui::Color32::BLACK);
+
+        // egui resends the image with the new options, which reuses the
+        // texture and switches the sampler.
+        handle.set(black_and_white(), egui::TextureOptions::LINEAR);
+        let linear =
+            render_filtered(&mut renderer, &ctx, &egui_ctx, handle.id());
+        assert!(linear.r() > 0 && linear.r() < 128, "{linear:?}");
+        handle.set(black_and_white(), egui::TextureOptions::NEAREST);
+        let nearest =
+            render_filtered(&mut renderer, &ctx, &egui_ctx, handle.id());
+        assert_eq!(nearest, egui::Color32::BLACK);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn native_texture_filter_follows_set_texture_options() {
+        let (_, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let egui_ctx = egui::Context::default();
+        let texels = [0, 0, 0, 255, 255, 255, 255, 255];
+        let texture = |renderer: &Renderer| {
+            native_texture(
+                renderer.device(),
+                DXGI_FORMAT_R8G8B8A8_UNORM,
+                [2, 1],
+                &texels,
+                8,
+            )
+        };
+        let tid = renderer
+            .register_native_texture(texture(&renderer))
+            .unwrap();
+
+        renderer
+            .set_texture_options(tid, egui::TextureOptions::NEAREST)
+            .unwrap();
+        let nearest = render_filtered(&mut renderer, &ctx, &egui_ctx, tid);
+        assert_eq!(nearest, egui::Color32::BLACK);
+        renderer
+            .set_texture_options(tid, egui::TextureOptions::LINEAR)
+            .unwrap();
+        let linear = render_filtered(&mut renderer, &ctx, &egui_ctx, tid);
+        assert!(linear.r() > 0 && linear.r() < 128, "{linear:?}");
+
+        // replacing the texture keeps its options.
+        renderer
+            .set_texture_options(tid, egui::TextureOptions::NEAREST)
+            .unwrap();
+        renderer
+            .update_native_texture(tid, texture(&renderer))
+            .unwrap();
+        let nearest = render_filtered(&mut renderer, &ctx, &egui_ctx, tid);
+        assert_eq!(nearest, egui::Color32::BLACK);
+    }
+
+    #[cfg(feature = "test-util")]
+    fn assert_rgb_near(color: egui::Color32, [r, g, b]: [u8; 3]) {
+        assert!(
+            color.r().abs_diff(r) <= 2
+                && color.g().abs_diff(g) <= 2
+                && color.b().abs_diff(b) <= 2,
+            "{color:?} is not near {:?}",
+            [r, g, b],
+        );
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn straight_alpha_textures_mix_with_widgets() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        // half-transparent dark blue, which would be brighter than opaque blue
+        // if blended as premultiplied.
+        let texture = native_texture(
+            &device,
+            DXGI_FORMAT_R8G8B8A8_UNORM,
+            [1, 1],
+            &[0, 0, 128, 128],
+            4,
+        );
+        let tid = renderer
+            .register_native_texture_ex(
+                texture,
+                NativeTextureOptions {
+                    alpha_mode: AlphaMode::Straight,
+                    ..NativeTextureOptions::default()
+                },
+            )
+            .unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, |egui_ctx| {
+            let painter = egui_ctx.layer_painter(egui::LayerId::background());
+            painter.rect_filled(
+                egui::Rect::EVERYTHING,
+                0.,
+                egui::Color32::WHITE,
+            );
+            paint_image(
+                egui_ctx,
+                tid,
+                egui::Rect::from_min_max(
+                    egui::Pos2::ZERO,
+                    egui::pos2(32., 64.),
+                ),
+                egui::Color32::WHITE,
+            );
+            // a premultiplied widget after it, which would be darker if
+            // blended as straight alpha.
+            painter.rect_filled(
+                egui::Rect::from_min_max(
+                    egui::pos2(32., 0.),
+                    egui::pos2(64., 64.),
+                ),
+                0.,
+                egui::Color32::from_rgba_premultiplied(128, 0, 0, 128),
+            );
+        });
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+
+        assert_rgb_near(pixel(&ctx, &tex, [16, 32]), [127, 127, 191]);
+        assert_rgb_near(pixel(&ctx, &tex, [48, 32]), [255, 127, 127]);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn managed_textures_and_text_draw_with_their_own_textures() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let egui_ctx = egui::Context::default();
+        let load = |name, color| {
+            egui_ctx.load_texture(
+                name,
+                egui::ColorImage::new([1, 1], vec![color]),
+                egui::TextureOptions::NEAREST,
+            )
+        };
+        let red = load("red", egui::Color32::RED);
+        let green = load("green", egui::Color32::GREEN);
+        let (tex, rtv) = render_target(&device, &ctx);
+        let output = run(&egui_ctx, |egui_ctx| {
+            let painter = egui_ctx.layer_painter(egui::LayerId::background());
+            painter.rect_filled(
+                egui::Rect::EVERYTHING,
+                0.,
+                egui::Color32::WHITE,
+            );
+            // interleaved with the font texture, so each draw must be bound
+            // to its own texture.
+            let corner = |min: egui::Pos2| {
+                egui::Rect::from_min_size(min, egui::vec2(16., 16.))
+            };
+            paint_image(
+                egui_ctx,
+                red.id(),
+                corner(egui::Pos2::ZERO),
+                egui::Color32::WHITE,
+            );
+            painter.text(
+                egui::pos2(32., 32.),
+                egui::Align2::CENTER_CENTER,
+                "W",
+                egui::FontId::proportional(24.),
+                egui::Color32::BLACK,
+            );
+            paint_image(
+                egui_ctx,
+                green.id(),
+                corner(egui::pos2(48., 48.)),
+                egui::Color32::WHITE,
+            );
+            paint_image(
+                egui_ctx,
+                red.id(),
+                corner(egui::pos2(0., 48.)),
+                egui::Color32::WHITE,
+            );
+        });
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+
+        let pixels = texture::tests::read_back(&ctx, &tex);
+        let at = |x: usize, y: usize| pixels[y * SIZE as usize + x];
+        assert_eq!(at(8, 8), egui::Color32::RED);
+        assert_eq!(at(56, 56), egui::Color32::GREEN);
+        assert_eq!(at(8, 56), egui::Color32::RED);
+        assert_eq!(at(56, 8), egui::Color32::WHITE);
+        let darkest = (20..44)
+            .flat_map(|y| (20..44).map(move |x| (x, y)))
+            .map(|(x, y)| at(x, y).g())
+            .min()
+            .unwrap();
+        assert!(darkest < 64, "text is missing, darkest green is {darkest}");
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn unmanaged_states_keep_the_host_rasterizer_state() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        // without the backup, so the state must be left alone rather than
+        // restored.
+        renderer.set_preserve_state(false);
+        renderer.set_manage_states(false);
+        let desc = D3D11_RASTERIZER_DESC {
+            FillMode: D3D11_FILL_SOLID,
+            CullMode: D3D11_CULL_NONE,
+            ScissorEnable: true.into(),
+            ..Default::default()
+        };
+        let mut state = None;
+        unsafe { device.CreateRasterizerState(&desc, Some(&mut state)) }
+            .unwrap();
+        let state = state.unwrap();
+        unsafe { ctx.RSSetState(&state) };
+
+        let (_, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, paint_rect);
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+
+        assert_eq!(unsafe { ctx.RSGetState() }.ok(), Some(state));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn mask_textures_are_tinted_by_the_vertex_color() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        // full coverage on the left, none on the right.
+        let mask =
+            native_texture(&device, DXGI_FORMAT_R8_UNORM, [2, 1], &[255, 0], 2);
+        let tid = renderer.register_mask_texture(mask).unwrap();
+        renderer
+            .set_texture_options(tid, egui::TextureOptions::NEAREST)
+            .unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, |egui_ctx| {
+            let half = |top: f32| {
+                egui::Rect::from_min_size(
+                    egui::pos2(0., top),
+                    egui::vec2(64., 32.),
+                )
+            };
+            paint_image(egui_ctx, tid, half(0.), egui::Color32::RED);
+            paint_image(egui_ctx, tid, half(32.), egui::Color32::GREEN);
+        });
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+
+        assert_eq!(pixel(&ctx, &tex, [16, 16]), egui::Color32::RED);
+        assert_eq!(pixel(&ctx, &tex, [16, 48]), egui::Color32::GREEN);
+        assert_eq!(pixel(&ctx, &tex, [48, 16]), egui::Color32::TRANSPARENT);
+        assert_eq!(pixel(&ctx, &tex, [48, 48]), egui::Color32::TRANSPARENT);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn render_records_into_deferred_contexts() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let mut deferred = None;
+        unsafe { device.CreateDeferredContext(0, Some(&mut deferred)) }
+            .unwrap();
+        let deferred = deferred.unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        // the first output also uploads the font atlas, which is recorded too.
+        let output = run(&egui_ctx, paint_rect);
+        renderer.render(&deferred, &rtv, &egui_ctx, output).unwrap();
+        assert_eq!(pixel(&ctx, &tex, [32, 32]), egui::Color32::TRANSPARENT);
+
+        let mut command_list = None;
+        unsafe { deferred.FinishCommandList(false, Some(&mut command_list)) }
+            .unwrap();
+        unsafe { ctx.ExecuteCommandList(&command_list.unwrap(), false) };
+        assert_eq!(pixel(&ctx, &tex, [32, 32]), egui::Color32::RED);
+        assert_eq!(pixel(&ctx, &tex, [8, 8]), egui::Color32::TRANSPARENT);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn render_returns_an_empty_dirty_rect_for_empty_output() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let (_, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, |_| {});
+        assert!(output.shapes.is_empty());
+        let rect = renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+        assert!(rect == Some(RECT::default()));
+
+        // shapes outside of the frame draw nothing either.
+        let output = run(&egui_ctx, |egui_ctx| {
+            egui_ctx
+                .layer_painter(egui::LayerId::background())
+                .rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(100., 100.),
+                        egui::pos2(200., 200.),
+                    ),
+                    0.,
+                    egui::Color32::RED,
+                );
+        });
+        let rect = renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+        assert!(rect == Some(RECT::default()));
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn render_raw_clips_to_the_clip_rect() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        // upload the font atlas, whose top left texel is white.
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, |_| {});
+        renderer
+            .update_textures(&ctx, output.textures_delta)
+            .unwrap();
+
+        let mut vertices = Vec::new();
+        for [x, y] in [[0., 0.], [64., 0.], [0., 64.], [64., 64.]] {
+            for value in [x, y, 0., 0.] {
+                vertices.extend_from_slice(&f32::to_ne_bytes(value));
+            }
+            vertices.extend_from_slice(&[255; 4]);
+        }
+        let indices = [0u32, 1, 2, 2, 1, 3]
+            .iter()
+            .flat_map(|index| index.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let draw = RawDrawCommand {
+            clip_rect: [0., 0., 32., 64.],
+            index_count: 6,
+            ..RawDrawCommand::default()
+        };
+        renderer
+            .render_raw(&ctx, &rtv, &vertices, &indices, &[draw], 1.)
+            .unwrap();
+
+        assert_eq!(pixel(&ctx, &tex, [16, 32]), egui::Color32::WHITE);
+        assert_eq!(pixel(&ctx, &tex, [48, 32]), egui::Color32::TRANSPARENT);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn texture_slot_does_not_change_the_output() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let limit = D3D11_COMMONSHADER_INPUT_RESOURCE_SLOT_COUNT - 1;
+        assert!(renderer.set_texture_slot(limit).is_err());
+        if cfg!(feature = "precompiled") {
+            assert!(renderer.set_texture_slot(2).is_err());
+            return;
+        }
+        renderer.set_texture_slot(2).unwrap();
+
+        let (tex, rtv) = render_target(&device, &ctx);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, paint_rect);
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+        assert_eq!(pixel(&ctx, &tex, [32, 32]), egui::Color32::RED);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn opaque_composite_mode_overwrites_the_target() {
+        let (device, ctx, mut renderer) = Renderer::new_warp().unwrap();
+        let (tex, rtv) = render_target(&device, &ctx);
+        unsafe { ctx.ClearRenderTargetView(&rtv, &[1.; 4]) };
+        renderer.set_composite_mode(CompositeMode::Opaque);
+        let egui_ctx = egui::Context::default();
+        let output = run(&egui_ctx, |egui_ctx| {
+            egui_ctx
+                .layer_painter(egui::LayerId::background())
+                .rect_filled(
+                    egui::Rect::from_min_max(
+                        egui::pos2(16., 16.),
+                        egui::pos2(48., 48.),
+                    ),
+                    0.,
+                    egui::Color32::from_black_alpha(128),
+                );
+        });
+        renderer.render(&ctx, &rtv, &egui_ctx, output).unwrap();
+        assert_eq!(
+            pixel(&ctx, &tex, [32, 32]),
+            egui::Color32::from_black_alpha(128)
+        );
+        assert_eq!(pixel(&ctx, &tex, [4, 4]), egui::Color32::WHITE);
     }
 }
@@ -0,0 +1,159 @@
+// Optional handling of the platform output of `egui` with plain Win32 APIs,
+// for applications that do not use `egui-winit`.
+
+use egui::{CursorIcon, OutputCommand, PlatformOutput};
+
+use windows::Win32::{
+    Foundation::{E_FAIL, GlobalFree, HANDLE, HWND},
+    System::{
+        DataExchange::{
+            CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+        },
+        Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock},
+    },
+    UI::{Shell::ShellExecuteW, WindowsAndMessaging::*},
+};
+use windows::core::{Error, HSTRING, Result, w};
+
+// `CF_UNICODETEXT` of `Win32_System_Ole`, which is not needed otherwise.
+const CF_UNICODETEXT: u32 = 13;
+
+/// Act on the parts of `platform_output` which need the operating system,
+/// for integrations using plain Win32 instead of `egui-winit`. Requires the
+/// `win32-platform` feature.
+///
+/// + The cursor is set to [`PlatformOutput::cursor_icon`], see
+///   [`set_cursor_win32`];
+/// + Copied text is written to the clipboard, owned by `hwnd`;
+/// + URLs are opened with `ShellExecuteW`, i.e. in the default browser.
+///   [`egui::OpenUrl::new_tab`] is ignored.
+///
+/// Copying images is not supported, and is ignored with a warning. Events,
+/// IME and accessibility output are left to the application. All commands
+/// are attempted, and the first error is returned, e.g. if the clipboard
+/// is opened by another application.
+///
+/// This function must be called on the thread that created `hwnd`.
+pub fn handle_platform_output_win32(
+    platform_output: &PlatformOutput,
+    hwnd: HWND,
+) -> Result<()> {
+    set_cursor_win32(platform_output.cursor_icon)?;
+    let mut result = Ok(());
+    for command in &platform_output.commands {
+        let command_result = match command {
+            OutputCommand::CopyText(text) => copy_text(hwnd, text),
+            OutputCommand::CopyImage(_) => {
+                log::warn!(concat!(
+                    "egui wants to copy an image to the clipboard, which is not supported. ",
+                    "this request will be ignored."
+                ));
+                Ok(())
+            },
+            OutputCommand::OpenUrl(open_url) => {
+                open_url_win32(hwnd, &open_url.url)
+            },
+        };
+        if result.is_ok() {
+            result = command_result;
+        }
+    }
+    result
+}
+
+/// Set the cursor of the calling thread to the system cursor closest to
+/// `icon`, or hide it for [`CursorIcon::None`]. Requires the
+/// `win32-platform` feature.
+///
+/// Windows sets the cursor to the cursor of the window class whenever the
+/// mouse moves, unless the window handles `WM_SETCURSOR`. So either register
+/// the window class without a cursor, or call this function again with the
+/// latest [`PlatformOutput::cursor_icon`] when handling `WM_SETCURSOR` over
+/// the client area, and return `TRUE` from the window procedure.
+pub fn set_cursor_win32(icon: CursorIcon) -> Result<()> {
+    use CursorIcon::*;
+
+    let name = match icon {
+        None => {
+            unsafe { SetCursor(Option::None) };
+            return Ok(());
+        },
+        Default | ContextMenu | Alias | Copy | ZoomIn | ZoomOut => IDC_ARROW,
+        Help => IDC_HELP,
+        PointingHand => IDC_HAND,
+        Progress => IDC_APPSTARTING,
+        Wait => IDC_WAIT,
+        Cell | Crosshair => IDC_CROSS,
+        Text | VerticalText => IDC_IBEAM,
+        Move | Grab | Grabbing | AllScroll => IDC_SIZEALL,
+        NoDrop | NotAllowed => IDC_NO,
+        ResizeHorizontal | ResizeEast | ResizeWest | ResizeColumn => IDC_SIZEWE,
+        ResizeVertical | ResizeNorth | ResizeSouth | ResizeRow => IDC_SIZENS,
+        ResizeNeSw | ResizeNorthEast | ResizeSouthWest => IDC_SIZENESW,
+        ResizeNwSe | ResizeNorthWest | ResizeSouthEast => IDC_SIZENWSE,
+    };
+    let cursor = unsafe { LoadCursorW(Option::None, name) }?;
+    unsafe { SetCursor(Some(cursor)) };
+    Ok(())
+}
+
+fn copy_text(hwnd: HWND, text: &str) -> Result<()> {
+    let utf16 = text.encode_utf16().chain([0]).collect::<Vec<_>>();
+    unsafe { OpenClipboard(Some(hwnd)) }.map_err(|err| {
+        Error::new(
+            err.code(),
+            format!("failed to open the clipboard: {}", err.message()),
+        )
+    })?;
+    let result = unsafe { EmptyClipboard() }.and_then(|()| {
+        let memory = unsafe { GlobalAlloc(GMEM_MOVEABLE, utf16.len() * 2) }?;
+        unsafe {
+            let data = GlobalLock(memory) as *mut u16;
+            if data.is_null() {
+                let _ = GlobalFree(Some(memory));
+                return Err(Error::from_thread());
+            }
+            data.copy_from_nonoverlapping(utf16.as_ptr(), utf16.len());
+            // fails with `NO_ERROR` once the memory is unlocked.
+            let _ = GlobalUnlock(memory);
+        }
+        // the clipboard owns the memory once the data is set.
+        if let Err(err) =
+            unsafe { SetClipboardData(CF_UNICODETEXT, Some(HANDLE(memory.0))) }
+        {
+            let _ = unsafe { GlobalFree(Some(memory)) };
+            return Err(err);
+        }
+        Ok(())
+    });
+    let _ = unsafe { CloseClipboard() };
+    result.map_err(|err| {
+        Error::new(
+            err.code(),
+            format!("failed to copy text to the clipboard: {}", err.message()),
+        )
+    })
+}
+
+fn open_url_win32(hwnd: HWND, url: &str) -> Result<()> {
+    let instance = unsafe {
+        ShellExecuteW(
+            Some(hwnd),
+            w!("open"),
+            &HSTRING::from(url),
+            None,
+            None,
+            SW_SHOWNORMAL,
+        )
+    };
+    // values up to 32 are error codes, see the documentation of
+    // `ShellExecuteW`.
+    let code = instance.0 as isize;
+    if code <= 32 {
+        return Err(Error::new(
+            E_FAIL,
+            format!("failed to open url {url:?}: error {code}"),
+        ));
+    }
+    Ok(())
+}
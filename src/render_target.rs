@@ -0,0 +1,158 @@
+use windows::Win32::Foundation::E_INVALIDARG;
+use windows::Win32::Graphics::{Direct3D11::*, Dxgi::Common::*};
+use windows::core::{Error, Interface, Result};
+
+use crate::zeroed;
+
+/// Maximum number of render target views kept alive by the cache. Most
+/// applications render to one or two targets per frame (e.g. a back buffer
+/// and an offscreen texture), so a handful of slots is plenty.
+const CAPACITY: usize = 4;
+
+struct Entry {
+    resource: usize,
+    format: DXGI_FORMAT,
+    // mip level and array slice, if the view is restricted to one.
+    subresource: Option<(u32, u32)>,
+    size: (u32, u32),
+    rtv: ID3D11RenderTargetView,
+}
+
+/// A small LRU cache of render target views keyed by the raw pointer, the
+/// format and the viewed subresource of the underlying texture.
+///
+/// Note that each cached view holds a reference to its texture, so a cached
+/// texture will not be released until it is evicted or the cache is cleared.
+pub struct RenderTargetCache {
+    // most recently used entry comes first.
+    entries: Vec<Entry>,
+}
+
+impl RenderTargetCache {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::with_capacity(CAPACITY),
+        }
+    }
+
+    pub fn get_or_create(
+        &mut self,
+        device: &ID3D11Device,
+        texture: &ID3D11Texture2D,
+    ) -> Result<ID3D11RenderTargetView> {
+        self.get_or_create_subresource(device, texture, None)
+    }
+
+    /// Get a view of the texture restricted to `subresource`, i.e. a mip level
+    /// and an array slice, or of the whole texture if it is `None`.
+    pub fn get_or_create_subresource(
+        &mut self,
+        device: &ID3D11Device,
+        texture: &ID3D11Texture2D,
+        subresource: Option<(u32, u32)>,
+    ) -> Result<ID3D11RenderTargetView> {
+        let mut desc = zeroed::<D3D11_TEXTURE2D_DESC>();
+        unsafe { texture.GetDesc(&mut desc) };
+
+        let view_desc = subresource
+            .map(|(mip, slice)| Self::subresource_view_desc(&desc, mip, slice))
+            .transpose()?;
+
+        let resource = texture.as_raw() as usize;
+        let size = (desc.Width, desc.Height);
+        if let Some(idx) = self.entries.iter().position(|entry| {
+            entry.resource == resource
+                && entry.format == desc.Format
+                && entry.subresource == subresource
+        }) {
+            let entry = self.entries.remove(idx);
+            if entry.size == size {
+                let rtv = entry.rtv.clone();
+                self.entries.insert(0, entry);
+                return Ok(rtv);
+            }
+        }
+
+        let mut rtv = None;
+        unsafe {
+            device.CreateRenderTargetView(
+                texture,
+                view_desc.as_ref().map(|desc| desc as *const _),
+                Some(&mut rtv),
+            )
+        }?;
+        let rtv = rtv.unwrap();
+        self.entries.truncate(CAPACITY - 1);
+        self.entries.insert(
+            0,
+            Entry {
+                resource,
+                format: desc.Format,
+                subresource,
+                size,
+                rtv: rtv.clone(),
+            },
+        );
+        Ok(rtv)
+    }
+
+    fn subresource_view_desc(
+        desc: &D3D11_TEXTURE2D_DESC,
+        mip: u32,
+        slice: u32,
+    ) -> Result<D3D11_RENDER_TARGET_VIEW_DESC> {
+        let multisampled = desc.SampleDesc.Count > 1;
+        if mip >= desc.MipLevels || (multisampled && mip > 0) {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "mip level {mip} is out of range for a texture with {} levels",
+                    if multisampled { 1 } else { desc.MipLevels },
+                ),
+            ));
+        }
+        if slice >= desc.ArraySize {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "array slice {slice} is out of range for a texture with {} slices",
+                    desc.ArraySize,
+                ),
+            ));
+        }
+        let mut view_desc = D3D11_RENDER_TARGET_VIEW_DESC {
+            Format: desc.Format,
+            ..Default::default()
+        };
+        match (desc.ArraySize > 1, multisampled) {
+            (false, false) => {
+                view_desc.ViewDimension = D3D11_RTV_DIMENSION_TEXTURE2D;
+                view_desc.Anonymous.Texture2D.MipSlice = mip;
+            },
+            (false, true) => {
+                view_desc.ViewDimension = D3D11_RTV_DIMENSION_TEXTURE2DMS;
+            },
+            (true, false) => {
+                view_desc.ViewDimension = D3D11_RTV_DIMENSION_TEXTURE2DARRAY;
+                view_desc.Anonymous.Texture2DArray = D3D11_TEX2D_ARRAY_RTV {
+                    MipSlice: mip,
+                    FirstArraySlice: slice,
+                    ArraySize: 1,
+                };
+            },
+            (true, true) => {
+                view_desc.ViewDimension = D3D11_RTV_DIMENSION_TEXTURE2DMSARRAY;
+                view_desc.Anonymous.Texture2DMSArray =
+                    D3D11_TEX2DMS_ARRAY_RTV {
+                        FirstArraySlice: slice,
+                        ArraySize: 1,
+                    };
+            },
+        }
+        Ok(view_desc)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
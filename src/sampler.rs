@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use egui::{TextureFilter, TextureOptions, TextureWrapMode};
+
+use windows::Win32::Graphics::Direct3D11::*;
+use windows::core::Result;
+
+/// How texture coordinates outside of `0..1` are resolved when sampling a
+/// texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressMode {
+    /// Use the texel on the edge of the texture.
+    Clamp,
+    /// Repeat the texture.
+    Repeat,
+    /// Repeat the texture, mirroring it on every repetition.
+    Mirror,
+    /// Use the provided `color`, which is premultiplied as all colors in
+    /// `egui`. Use a transparent color (i.e. `[0.0; 4]`) to avoid bleeding
+    /// of edge texels when drawing a texture slightly outside of its bounds.
+    ClampToBorder {
+        #[allow(missing_docs)]
+        color: [f32; 4],
+    },
+}
+
+/// Options for sampling a texture, used to select the sampler state bound
+/// when drawing with the texture.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerOptions {
+    /// Filter used when the texture is magnified.
+    pub magnification: TextureFilter,
+    /// Filter used when the texture is minified. This also applies to
+    /// filtering between mip levels.
+    pub minification: TextureFilter,
+    /// How texture coordinates outside of `0..1` are resolved.
+    pub address_mode: AddressMode,
+    /// Offset added to the mip level selected by the GPU, e.g. a negative
+    /// value for a sharper but more aliased image when minifying.
+    pub mip_lod_bias: f32,
+    /// The most detailed mip level to sample, where `0.0` is the largest.
+    pub min_lod: f32,
+    /// The least detailed mip level to sample, after applying the bias.
+    /// Defaults to `0.0`, so that only the largest mip level is sampled, as
+    /// for textures without mipmaps. Use [`f32::MAX`] to sample all mip
+    /// levels of a mipmapped texture.
+    pub max_lod: f32,
+    /// The maximum anisotropy for anisotropic filtering, between 2 and 16,
+    /// which sharpens textures viewed at steep angles or scaled unevenly.
+    /// Both filters are ignored when anisotropic filtering is enabled.
+    /// Defaults to `1`, i.e. disabled. Values above 16 are clamped.
+    pub max_anisotropy: u32,
+}
+
+impl Default for SamplerOptions {
+    fn default() -> Self {
+        Self {
+            magnification: TextureFilter::Linear,
+            minification: TextureFilter::Linear,
+            address_mode: AddressMode::ClampToBorder { color: [1.; 4] },
+            mip_lod_bias: 0.0,
+            min_lod: 0.0,
+            max_lod: 0.0,
+            max_anisotropy: 1,
+        }
+    }
+}
+
+/// Sample as `egui` requests for textures managed by it. Mipmaps are not
+/// used, so [`TextureOptions::mipmap_mode`] is ignored.
+impl From<TextureOptions> for SamplerOptions {
+    fn from(options: TextureOptions) -> Self {
+        Self {
+            magnification: options.magnification,
+            minification: options.minification,
+            address_mode: match options.wrap_mode {
+                TextureWrapMode::ClampToEdge => AddressMode::Clamp,
+                TextureWrapMode::Repeat => AddressMode::Repeat,
+                TextureWrapMode::MirroredRepeat => AddressMode::Mirror,
+            },
+            ..Self::default()
+        }
+    }
+}
+
+impl SamplerOptions {
+    fn desc(&self) -> D3D11_SAMPLER_DESC {
+        use TextureFilter::{Linear as L, Nearest as N};
+
+        let filter = match (self.minification, self.magnification) {
+            _ if self.max_anisotropy > 1 => D3D11_FILTER_ANISOTROPIC,
+            (N, N) => D3D11_FILTER_MIN_MAG_MIP_POINT,
+            (N, L) => D3D11_FILTER_MIN_POINT_MAG_LINEAR_MIP_POINT,
+            (L, N) => D3D11_FILTER_MIN_LINEAR_MAG_POINT_MIP_LINEAR,
+            (L, L) => D3D11_FILTER_MIN_MAG_MIP_LINEAR,
+        };
+        let (address, border_color) = match self.address_mode {
+            AddressMode::Clamp => (D3D11_TEXTURE_ADDRESS_CLAMP, [0.; 4]),
+            AddressMode::Repeat => (D3D11_TEXTURE_ADDRESS_WRAP, [0.; 4]),
+            AddressMode::Mirror => (D3D11_TEXTURE_ADDRESS_MIRROR, [0.; 4]),
+            AddressMode::ClampToBorder { color } => {
+                (D3D11_TEXTURE_ADDRESS_BORDER, color)
+            },
+        };
+        D3D11_SAMPLER_DESC {
+            Filter: filter,
+            AddressU: address,
+            AddressV: address,
+            AddressW: address,
+            ComparisonFunc: D3D11_COMPARISON_ALWAYS,
+            BorderColor: border_color,
+            MipLODBias: self.mip_lod_bias,
+            MinLOD: self.min_lod,
+            MaxLOD: self.max_lod,
+            MaxAnisotropy: self
+                .max_anisotropy
+                .clamp(1, D3D11_REQ_MAXANISOTROPY),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SamplerKey {
+    filter: i32,
+    address: i32,
+    border_color: [u32; 4],
+    lod: [u32; 3],
+    max_anisotropy: u32,
+}
+
+impl From<&D3D11_SAMPLER_DESC> for SamplerKey {
+    fn from(desc: &D3D11_SAMPLER_DESC) -> Self {
+        Self {
+            filter: desc.Filter.0,
+            address: desc.AddressU.0,
+            border_color: desc.BorderColor.map(f32::to_bits),
+            lod: [desc.MipLODBias, desc.MinLOD, desc.MaxLOD].map(f32::to_bits),
+            max_anisotropy: desc.MaxAnisotropy,
+        }
+    }
+}
+
+/// Sampler states created so far, keyed by their descriptions.
+#[derive(Clone)]
+pub struct SamplerCache {
+    device: ID3D11Device,
+    samplers: HashMap<SamplerKey, ID3D11SamplerState>,
+}
+
+impl SamplerCache {
+    pub fn new(device: &ID3D11Device) -> Self {
+        Self {
+            device: device.clone(),
+            samplers: HashMap::new(),
+        }
+    }
+
+    pub fn get(
+        &mut self,
+        options: &SamplerOptions,
+    ) -> Result<ID3D11SamplerState> {
+        let desc = options.desc();
+        let key = SamplerKey::from(&desc);
+        if let Some(sampler) = self.samplers.get(&key) {
+            return Ok(sampler.clone());
+        }
+
+        let mut sampler = None;
+        unsafe { self.device.CreateSamplerState(&desc, Some(&mut sampler)) }?;
+        let sampler = sampler.unwrap();
+        self.samplers.insert(key, sampler.clone());
+        Ok(sampler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn egui_options_map_to_address_modes() {
+        for (wrap_mode, address_mode) in [
+            (TextureWrapMode::ClampToEdge, AddressMode::Clamp),
+            (TextureWrapMode::Repeat, AddressMode::Repeat),
+            (TextureWrapMode::MirroredRepeat, AddressMode::Mirror),
+        ] {
+            let options = SamplerOptions::from(TextureOptions {
+                wrap_mode,
+                ..TextureOptions::NEAREST
+            });
+            assert_eq!(options.address_mode, address_mode);
+            assert_eq!(options.magnification, TextureFilter::Nearest);
+            assert_eq!(options.minification, TextureFilter::Nearest);
+            assert!(options.max_lod == 0. && options.max_anisotropy == 1);
+        }
+    }
+
+    #[test]
+    fn filters_and_anisotropy_map_to_descs() {
+        use TextureFilter::{Linear as L, Nearest as N};
+
+        for (minification, magnification, filter) in [
+            (N, N, D3D11_FILTER_MIN_MAG_MIP_POINT),
+            (N, L, D3D11_FILTER_MIN_POINT_MAG_LINEAR_MIP_POINT),
+            (L, N, D3D11_FILTER_MIN_LINEAR_MAG_POINT_MIP_LINEAR),
+            (L, L, D3D11_FILTER_MIN_MAG_MIP_LINEAR),
+        ] {
+            let options = SamplerOptions {
+                minification,
+                magnification,
+                ..SamplerOptions::default()
+            };
+            assert_eq!(options.desc().Filter, filter);
+        }
+        let desc = SamplerOptions {
+            max_anisotropy: 64,
+            ..SamplerOptions::default()
+        }
+        .desc();
+        assert_eq!(desc.Filter, D3D11_FILTER_ANISOTROPIC);
+        assert_eq!(desc.MaxAnisotropy, D3D11_REQ_MAXANISOTROPY);
+    }
+
+    #[test]
+    fn keys_tell_apart_every_option() {
+        let key = |options: SamplerOptions| SamplerKey::from(&options.desc());
+        let base = SamplerOptions::default();
+        assert!(key(base) == key(base));
+        for options in [
+            SamplerOptions {
+                magnification: TextureFilter::Nearest,
+                ..base
+            },
+            SamplerOptions {
+                address_mode: AddressMode::Repeat,
+                ..base
+            },
+            SamplerOptions {
+                address_mode: AddressMode::ClampToBorder { color: [0.; 4] },
+                ..base
+            },
+            SamplerOptions {
+                mip_lod_bias: -0.5,
+                ..base
+            },
+            SamplerOptions {
+                min_lod: 1.,
+                ..base
+            },
+            SamplerOptions {
+                max_lod: f32::MAX,
+                ..base
+            },
+            SamplerOptions {
+                max_anisotropy: 4,
+                ..base
+            },
+        ] {
+            assert!(key(options) != key(base), "{options:?}");
+        }
+    }
+}
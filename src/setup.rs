@@ -0,0 +1,206 @@
+// Optional helper for creating a device and a swap chain for a window, for
+// applications that do not need any control over them.
+
+use std::mem;
+
+use raw_window_handle::RawWindowHandle;
+
+use windows::Win32::{
+    Foundation::{E_INVALIDARG, HMODULE, HWND},
+    Graphics::{
+        Direct3D::*,
+        Direct3D11::*,
+        Dxgi::{Common::*, *},
+    },
+};
+use windows::core::{BOOL, Error, Interface, Result};
+
+use crate::Renderer;
+
+/// How the swap chain created by
+/// [`Renderer::create_device_and_swap_chain_with_config`] is set up and
+/// presented.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapChainConfig {
+    /// The number of buffers of the swap chain, between 2 and 16 for the flip
+    /// model. Defaults to 2. A third buffer lets rendering continue while two
+    /// frames are queued, at the cost of latency.
+    pub buffer_count: u32,
+    /// The number of vertical blanks to wait for when presenting with
+    /// [`SwapChainConfig::present`], between 0 and 4. Defaults to 1, i.e.
+    /// vsync. With 0, frames are presented right away.
+    pub sync_interval: u32,
+    /// Whether frames presented with a `sync_interval` of 0 may tear, for
+    /// uncapped frame rates and variable refresh rate displays. Defaults to
+    /// `false`. This is silently ignored if the system does not support
+    /// tearing, which requires Windows 10 and the flip model.
+    pub allow_tearing: bool,
+}
+
+impl Default for SwapChainConfig {
+    fn default() -> Self {
+        Self {
+            buffer_count: 2,
+            sync_interval: 1,
+            allow_tearing: false,
+        }
+    }
+}
+
+impl SwapChainConfig {
+    /// Present the back buffer of `swap_chain` with the configured sync
+    /// interval, allowing tearing if the swap chain has been created with
+    /// `DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING`, `sync_interval` is 0 and the
+    /// swap chain is not in exclusive fullscreen mode, as DXGI requires.
+    /// Unlike [`Renderer::present`], this silently presents without tearing
+    /// otherwise.
+    ///
+    /// Note that `IDXGISwapChain::ResizeBuffers` must be passed the flags the
+    /// swap chain has been created with, as found in
+    /// `IDXGISwapChain1::GetDesc1`, for tearing to stay allowed.
+    pub fn present(&self, swap_chain: &IDXGISwapChain1) -> Result<()> {
+        let desc = unsafe { swap_chain.GetDesc1() }?;
+        let mut fullscreen = BOOL(0);
+        unsafe { swap_chain.GetFullscreenState(Some(&mut fullscreen), None) }?;
+        let allow_tearing = self.sync_interval == 0
+            && !fullscreen.as_bool()
+            && desc.Flags & DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32 != 0;
+        Renderer::present(swap_chain, self.sync_interval, allow_tearing)
+    }
+}
+
+impl Renderer {
+    /// Create a Direct3D11 device on the default adapter, along with its
+    /// immediate context and a swap chain presenting to the provided window.
+    ///
+    /// This is a convenience for getting started, covering what most
+    /// applications need. The device is created at feature level 11.0 with
+    /// `D3D11_CREATE_DEVICE_BGRA_SUPPORT`. The swap chain has two
+    /// `DXGI_FORMAT_R8G8B8A8_UNORM` buffers of the provided `size` in pixels,
+    /// and uses the flip model (`DXGI_SWAP_EFFECT_FLIP_DISCARD`, or
+    /// `DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL` before Windows 10). Alt+Enter
+    /// fullscreen toggling by DXGI is disabled. See
+    /// [`Renderer::render_to_swap_chain`] for rendering to it.
+    ///
+    /// If you need more control, e.g. over the adapter or the debug layer,
+    /// create the device and the swap chain yourself. Only available with the
+    /// `setup` feature. This function fails if `window` is not a Win32 window.
+    pub fn create_device_and_swap_chain(
+        window: RawWindowHandle,
+        size: (u32, u32),
+    ) -> Result<(ID3D11Device, ID3D11DeviceContext, IDXGISwapChain1)> {
+        Self::create_device_and_swap_chain_with_config(
+            window,
+            size,
+            &SwapChainConfig::default(),
+        )
+    }
+
+    /// Create a Direct3D11 device and a swap chain for the provided window
+    /// like [`Renderer::create_device_and_swap_chain`], with the number of
+    /// buffers and tearing configured by `config`. Present the swap chain
+    /// with [`SwapChainConfig::present`] for the configured sync interval and
+    /// tearing to apply.
+    ///
+    /// This function fails if `window` is not a Win32 window, or if the
+    /// buffer count is not supported.
+    pub fn create_device_and_swap_chain_with_config(
+        window: RawWindowHandle,
+        size: (u32, u32),
+        config: &SwapChainConfig,
+    ) -> Result<(ID3D11Device, ID3D11DeviceContext, IDXGISwapChain1)> {
+        let RawWindowHandle::Win32(window) = window else {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "only Win32 window handles are supported",
+            ));
+        };
+        let hwnd = HWND(window.hwnd.get() as _);
+
+        let mut device = None;
+        let mut device_context = None;
+        unsafe {
+            D3D11CreateDevice(
+                None,
+                D3D_DRIVER_TYPE_HARDWARE,
+                HMODULE::default(),
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                Some(&[D3D_FEATURE_LEVEL_11_0]),
+                D3D11_SDK_VERSION,
+                Some(&mut device),
+                None,
+                Some(&mut device_context),
+            )
+        }?;
+        let device = device.unwrap();
+        let device_context = device_context.unwrap();
+
+        // use the factory that created the device.
+        let dxgi_factory: IDXGIFactory2 =
+            unsafe { device.cast::<IDXGIDevice>()?.GetAdapter()?.GetParent() }?;
+        let allow_tearing =
+            config.allow_tearing && Self::supports_tearing(&dxgi_factory);
+        let mut swap_chain_desc = DXGI_SWAP_CHAIN_DESC1 {
+            Width: size.0,
+            Height: size.1,
+            Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: config.buffer_count,
+            Scaling: DXGI_SCALING_STRETCH,
+            SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+            AlphaMode: DXGI_ALPHA_MODE_UNSPECIFIED,
+            Flags: if allow_tearing {
+                DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as _
+            } else {
+                0
+            },
+            ..Default::default()
+        };
+        let swap_chain = unsafe {
+            dxgi_factory.CreateSwapChainForHwnd(
+                &device,
+                hwnd,
+                &swap_chain_desc,
+                None,
+                None,
+            )
+        }
+        .or_else(|_| {
+            // `DXGI_SWAP_EFFECT_FLIP_DISCARD` requires Windows 10.
+            swap_chain_desc.SwapEffect = DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL;
+            unsafe {
+                dxgi_factory.CreateSwapChainForHwnd(
+                    &device,
+                    hwnd,
+                    &swap_chain_desc,
+                    None,
+                    None,
+                )
+            }
+        })?;
+
+        unsafe {
+            dxgi_factory.MakeWindowAssociation(hwnd, DXGI_MWA_NO_ALT_ENTER)
+        }?;
+        Ok((device, device_context, swap_chain))
+    }
+
+    fn supports_tearing(dxgi_factory: &IDXGIFactory2) -> bool {
+        let Ok(dxgi_factory) = dxgi_factory.cast::<IDXGIFactory5>() else {
+            return false;
+        };
+        let mut allow_tearing = BOOL(0);
+        let supported = unsafe {
+            dxgi_factory.CheckFeatureSupport(
+                DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+                &mut allow_tearing as *mut _ as _,
+                mem::size_of::<BOOL>() as _,
+            )
+        };
+        supported.is_ok() && allow_tearing.as_bool()
+    }
+}
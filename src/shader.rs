@@ -0,0 +1,147 @@
+// Shader bytecode used by the renderer.
+//
+// By default shaders are compiled from `shaders/egui.hlsl` at runtime using
+// `D3DCompile`. With the `precompiled` feature enabled, the build script
+// compiles them with `fxc` ahead of time and the bytecode is embedded into
+// the binary instead.
+
+use std::borrow::Cow;
+
+use windows::core::Result;
+
+pub struct Shader {
+    #[cfg_attr(feature = "precompiled", allow(dead_code))]
+    entry: &'static str,
+    #[cfg_attr(feature = "precompiled", allow(dead_code))]
+    target: &'static str,
+    #[cfg(feature = "precompiled")]
+    bytecode: &'static [u8],
+}
+
+macro_rules! shader {
+    ($entry:literal, $target:literal) => {
+        Shader {
+            entry: concat!($entry, "\0"),
+            target: concat!($target, "\0"),
+            #[cfg(feature = "precompiled")]
+            bytecode: include_bytes!(concat!(
+                env!("OUT_DIR"),
+                "/",
+                $entry,
+                ".cso"
+            )),
+        }
+    };
+}
+
+// keep in sync with `SHADERS` in `build.rs`.
+pub const VS_EGUI: Shader = shader!("vs_egui", "vs_5_0");
+pub const PS_EGUI: Shader = shader!("ps_egui", "ps_5_0");
+pub const PS_EGUI_STRAIGHT: Shader = shader!("ps_egui_straight", "ps_5_0");
+pub const PS_EGUI_STRAIGHT_PREMULTIPLIED: Shader =
+    shader!("ps_egui_straight_premultiplied", "ps_5_0");
+pub const PS_EGUI_MASK: Shader = shader!("ps_egui_mask", "ps_5_0");
+pub const PS_EGUI_NV12: Shader = shader!("ps_egui_nv12", "ps_5_0");
+pub const PS_EGUI_ID: Shader = shader!("ps_egui_id", "ps_5_0");
+pub const VS_BLIT: Shader = shader!("vs_blit", "vs_5_0");
+pub const PS_BLIT_DOWNSCALE: Shader = shader!("ps_blit_downscale", "ps_5_0");
+
+impl Shader {
+    pub fn bytecode(&self) -> Result<Cow<'static, [u8]>> {
+        self.bytecode_for_slot(0)
+    }
+
+    /// The bytecode with the texture bound to `texture_slot`, and the chroma
+    /// plane of video textures to the slot after it.
+    #[cfg(feature = "precompiled")]
+    pub fn bytecode_for_slot(
+        &self,
+        texture_slot: u32,
+    ) -> Result<Cow<'static, [u8]>> {
+        use windows::{Win32::Foundation::E_INVALIDARG, core::Error};
+
+        if texture_slot != 0 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "precompiled shaders only sample textures from slot 0, disable the `precompiled` feature to move them",
+            ));
+        }
+        Ok(Cow::Borrowed(self.bytecode))
+    }
+
+    /// The bytecode with the texture bound to `texture_slot`, and the chroma
+    /// plane of video textures to the slot after it.
+    #[cfg(not(feature = "precompiled"))]
+    pub fn bytecode_for_slot(
+        &self,
+        texture_slot: u32,
+    ) -> Result<Cow<'static, [u8]>> {
+        use std::slice;
+
+        use windows::Win32::Graphics::Direct3D::{
+            D3D_SHADER_MACRO, Fxc::*, ID3DBlob,
+        };
+        use windows::core::{PCSTR, s};
+
+        const SOURCE: &str = include_str!("../shaders/egui.hlsl");
+
+        let texture = format!("t{texture_slot}\0");
+        let chroma = format!("t{}\0", texture_slot + 1);
+        let defines = [
+            D3D_SHADER_MACRO {
+                Name: s!("TEXTURE_SLOT"),
+                Definition: PCSTR::from_raw(texture.as_ptr()),
+            },
+            D3D_SHADER_MACRO {
+                Name: s!("CHROMA_SLOT"),
+                Definition: PCSTR::from_raw(chroma.as_ptr()),
+            },
+            // terminates the list.
+            D3D_SHADER_MACRO::default(),
+        ];
+        let mut code = None::<ID3DBlob>;
+        let mut errors = None::<ID3DBlob>;
+        let result = unsafe {
+            D3DCompile(
+                SOURCE.as_ptr() as _,
+                SOURCE.len(),
+                s!("egui.hlsl"),
+                Some(defines.as_ptr()),
+                None,
+                PCSTR::from_raw(self.entry.as_ptr()),
+                PCSTR::from_raw(self.target.as_ptr()),
+                D3DCOMPILE_OPTIMIZATION_LEVEL3,
+                0,
+                &mut code,
+                Some(&mut errors),
+            )
+        };
+        if let Some(errors) = errors {
+            let errors = unsafe {
+                slice::from_raw_parts(
+                    errors.GetBufferPointer() as *const u8,
+                    errors.GetBufferSize(),
+                )
+            };
+            let entry = self.entry.trim_end_matches('\0');
+            let errors = String::from_utf8_lossy(errors);
+            if result.is_err() {
+                log::error!("failed to compile shader {entry}: {errors}");
+            } else {
+                log::warn!("shader {entry} compiled with warnings: {errors}");
+            }
+        }
+        result?;
+
+        let code = code.unwrap();
+        Ok(Cow::Owned(
+            unsafe {
+                slice::from_raw_parts(
+                    code.GetBufferPointer() as *const u8,
+                    code.GetBufferSize(),
+                )
+            }
+            .to_vec(),
+        ))
+    }
+}
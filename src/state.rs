@@ -0,0 +1,181 @@
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Graphics::{Direct3D::*, Direct3D11::*, Dxgi::Common::*};
+
+const MAX_VIEWPORTS: usize =
+    D3D11_VIEWPORT_AND_SCISSORRECT_OBJECT_COUNT_PER_PIPELINE as _;
+const MAX_RENDER_TARGETS: usize = D3D11_SIMULTANEOUS_RENDER_TARGET_COUNT as _;
+
+/// A backup of the part of the pipeline state that [`crate::Renderer`]
+/// overrides when rendering.
+///
+/// Class instances bound to the vertex and pixel shaders are not preserved.
+pub struct StateBackup {
+    input_layout: Option<ID3D11InputLayout>,
+    primitive_topology: D3D_PRIMITIVE_TOPOLOGY,
+    vertex_buffer: Option<ID3D11Buffer>,
+    vertex_buffer_stride: u32,
+    vertex_buffer_offset: u32,
+    index_buffer: Option<ID3D11Buffer>,
+    index_buffer_format: DXGI_FORMAT,
+    index_buffer_offset: u32,
+
+    vertex_shader: Option<ID3D11VertexShader>,
+    vertex_constant_buffer: [Option<ID3D11Buffer>; 3],
+
+    rasterizer_state: Option<ID3D11RasterizerState>,
+    viewports: Vec<D3D11_VIEWPORT>,
+    scissor_rects: Vec<RECT>,
+
+    pixel_shader: Option<ID3D11PixelShader>,
+    pixel_constant_buffer: [Option<ID3D11Buffer>; 3],
+    // the first slot saved, see `StateBackup::save`.
+    shader_resource_slot: u32,
+    shader_resources: [Option<ID3D11ShaderResourceView>; 2],
+    sampler: [Option<ID3D11SamplerState>; 1],
+
+    render_targets: [Option<ID3D11RenderTargetView>; MAX_RENDER_TARGETS],
+    depth_stencil_view: Option<ID3D11DepthStencilView>,
+    depth_stencil_state: Option<ID3D11DepthStencilState>,
+    stencil_ref: u32,
+    blend_state: Option<ID3D11BlendState>,
+    blend_factor: [f32; 4],
+    sample_mask: u32,
+}
+
+impl StateBackup {
+    /// Save the pipeline state, including the two shader resource slots from
+    /// `shader_resource_slot`.
+    pub fn save(ctx: &ID3D11DeviceContext, shader_resource_slot: u32) -> Self {
+        let mut this = Self {
+            input_layout: None,
+            primitive_topology: D3D_PRIMITIVE_TOPOLOGY_UNDEFINED,
+            vertex_buffer: None,
+            vertex_buffer_stride: 0,
+            vertex_buffer_offset: 0,
+            index_buffer: None,
+            index_buffer_format: DXGI_FORMAT_UNKNOWN,
+            index_buffer_offset: 0,
+            vertex_shader: None,
+            vertex_constant_buffer: Default::default(),
+            rasterizer_state: None,
+            viewports: vec![D3D11_VIEWPORT::default(); MAX_VIEWPORTS],
+            scissor_rects: vec![RECT::default(); MAX_VIEWPORTS],
+            pixel_shader: None,
+            pixel_constant_buffer: Default::default(),
+            shader_resource_slot,
+            shader_resources: Default::default(),
+            sampler: Default::default(),
+            render_targets: Default::default(),
+            depth_stencil_view: None,
+            depth_stencil_state: None,
+            stencil_ref: 0,
+            blend_state: None,
+            blend_factor: [0.; 4],
+            sample_mask: 0,
+        };
+        unsafe {
+            this.input_layout = ctx.IAGetInputLayout().ok();
+            this.primitive_topology = ctx.IAGetPrimitiveTopology();
+            ctx.IAGetVertexBuffers(
+                0,
+                1,
+                Some(&mut this.vertex_buffer),
+                Some(&mut this.vertex_buffer_stride),
+                Some(&mut this.vertex_buffer_offset),
+            );
+            ctx.IAGetIndexBuffer(
+                Some(&mut this.index_buffer),
+                Some(&mut this.index_buffer_format),
+                Some(&mut this.index_buffer_offset),
+            );
+
+            ctx.VSGetShader(&mut this.vertex_shader, None, None);
+            ctx.VSGetConstantBuffers(0, Some(&mut this.vertex_constant_buffer));
+
+            this.rasterizer_state = ctx.RSGetState().ok();
+            let mut num_viewports = MAX_VIEWPORTS as u32;
+            ctx.RSGetViewports(
+                &mut num_viewports,
+                Some(this.viewports.as_mut_ptr()),
+            );
+            this.viewports.truncate(num_viewports as _);
+            let mut num_scissor_rects = MAX_VIEWPORTS as u32;
+            ctx.RSGetScissorRects(
+                &mut num_scissor_rects,
+                Some(this.scissor_rects.as_mut_ptr()),
+            );
+            this.scissor_rects.truncate(num_scissor_rects as _);
+
+            ctx.PSGetShader(&mut this.pixel_shader, None, None);
+            ctx.PSGetConstantBuffers(0, Some(&mut this.pixel_constant_buffer));
+            ctx.PSGetShaderResources(
+                shader_resource_slot,
+                Some(&mut this.shader_resources),
+            );
+            ctx.PSGetSamplers(0, Some(&mut this.sampler));
+
+            ctx.OMGetRenderTargets(
+                Some(&mut this.render_targets),
+                Some(&mut this.depth_stencil_view),
+            );
+            ctx.OMGetDepthStencilState(
+                Some(&mut this.depth_stencil_state),
+                Some(&mut this.stencil_ref),
+            );
+            ctx.OMGetBlendState(
+                Some(&mut this.blend_state),
+                Some(&mut this.blend_factor),
+                Some(&mut this.sample_mask),
+            );
+        }
+        this
+    }
+
+    pub fn restore(self, ctx: &ID3D11DeviceContext) {
+        unsafe {
+            ctx.IASetInputLayout(self.input_layout.as_ref());
+            ctx.IASetPrimitiveTopology(self.primitive_topology);
+            ctx.IASetVertexBuffers(
+                0,
+                1,
+                Some(&self.vertex_buffer),
+                Some(&self.vertex_buffer_stride),
+                Some(&self.vertex_buffer_offset),
+            );
+            ctx.IASetIndexBuffer(
+                self.index_buffer.as_ref(),
+                self.index_buffer_format,
+                self.index_buffer_offset,
+            );
+
+            ctx.VSSetShader(self.vertex_shader.as_ref(), None);
+            ctx.VSSetConstantBuffers(0, Some(&self.vertex_constant_buffer));
+
+            ctx.RSSetState(self.rasterizer_state.as_ref());
+            ctx.RSSetViewports(Some(&self.viewports));
+            ctx.RSSetScissorRects(Some(&self.scissor_rects));
+
+            ctx.PSSetShader(self.pixel_shader.as_ref(), None);
+            ctx.PSSetConstantBuffers(0, Some(&self.pixel_constant_buffer));
+            ctx.PSSetShaderResources(
+                self.shader_resource_slot,
+                Some(&self.shader_resources),
+            );
+            ctx.PSSetSamplers(0, Some(&self.sampler));
+
+            ctx.OMSetRenderTargets(
+                Some(&self.render_targets),
+                self.depth_stencil_view.as_ref(),
+            );
+            ctx.OMSetDepthStencilState(
+                self.depth_stencil_state.as_ref(),
+                self.stencil_ref,
+            );
+            ctx.OMSetBlendState(
+                self.blend_state.as_ref(),
+                Some(&self.blend_factor),
+                self.sample_mask,
+            );
+        }
+    }
+}
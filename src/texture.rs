@@ -10,25 +10,233 @@
 
 use std::{collections::HashMap, mem, slice};
 
-use egui::{Color32, ImageData, TextureId, TexturesDelta};
+use egui::{
+    Color32, ColorImage, ImageData, TextureFilter, TextureId, TexturesDelta,
+};
+
+use crate::{AddressMode, SamplerOptions};
 
 use windows::{
-    Win32::Graphics::{Direct3D11::*, Dxgi::Common::*},
-    core::Result,
+    Win32::{
+        Foundation::E_INVALIDARG,
+        Graphics::{
+            Direct3D::*,
+            Direct3D11::*,
+            Dxgi::{Common::*, DXGI_ERROR_INVALID_CALL},
+        },
+    },
+    core::{Error, Interface, Result},
 };
 
 struct Texture {
     tex: ID3D11Texture2D,
     srv: ID3D11ShaderResourceView,
-    pixels: Vec<Color32>,
+    // a copy of the content if `TexturePool::keep_cpu_mirror` was enabled
+    // on creation. textures without one are created with
+    // `D3D11_USAGE_DEFAULT`, and updated with `UpdateSubresource`.
+    pixels: Option<Vec<Color32>>,
     width: usize,
+    height: usize,
+    sampler: SamplerOptions,
+}
+
+struct NativeTexture {
+    tex: ID3D11Texture2D,
+    srv: ID3D11ShaderResourceView,
+    video: Option<VideoPlanes>,
+    // whether the red channel is sampled as coverage.
+    mask: bool,
+    options: NativeTextureOptions,
+    // overrides `options.sampler` if set.
+    custom_sampler: Option<ID3D11SamplerState>,
+}
+
+/// Format of a video texture registered with
+/// [`crate::Renderer::register_video_texture`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum VideoFormat {
+    /// `DXGI_FORMAT_NV12`, i.e. an 8-bit luma plane followed by an 8-bit
+    /// chroma plane of half width and height, as commonly produced by video
+    /// decoders like Media Foundation. Colors are converted to RGB using the
+    /// BT.709 matrix with limited range.
+    Nv12,
+}
+
+/// The chroma plane of a video texture, whose luma plane is bound as the
+/// regular shader resource view.
+#[derive(Clone)]
+pub struct VideoPlanes {
+    pub format: VideoFormat,
+    pub chroma_srv: ID3D11ShaderResourceView,
+}
+
+/// Options for a native texture registered with
+/// [`crate::Renderer::register_native_texture_ex`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NativeTextureOptions {
+    /// How the texture is sampled.
+    pub sampler: SamplerOptions,
+    /// How the alpha channel of the texture is interpreted.
+    pub alpha_mode: AlphaMode,
+    /// A multiplier for the colors drawn with the texture in linear space,
+    /// e.g. for glowing widgets in HDR. Defaults to `1.0`.
+    ///
+    /// This only affects render targets receiving linear or HDR10 output (see
+    /// [`crate::Renderer::set_output_color_space`]), where colors above `1.0`
+    /// are kept by floating point formats, e.g. for bloom in a compositor.
+    /// Other formats clamp them as usual, and it is ignored for regular
+    /// SDR render targets, which receive colors in gamma space.
+    pub emissive: f32,
+    /// How the colors drawn with the texture are combined with the render
+    /// target.
+    pub blend_mode: BlendMode,
+    /// The color space of the texture, see [`TextureColorSpace`].
+    pub color_space: TextureColorSpace,
+}
+
+impl Default for NativeTextureOptions {
+    fn default() -> Self {
+        Self {
+            sampler: SamplerOptions::default(),
+            alpha_mode: AlphaMode::default(),
+            emissive: 1.,
+            blend_mode: BlendMode::default(),
+            color_space: TextureColorSpace::default(),
+        }
+    }
+}
+
+/// The color space of a native texture, i.e. the primaries of its colors.
+/// All color spaces are encoded with the sRGB transfer function, i.e. in
+/// gamma space as `egui` expects, and use the D65 white point.
+///
+/// Colors of wide gamut textures are converted to the primaries of the render
+/// target in the pixel shader. Colors outside of the sRGB gamut are kept for
+/// linear (scRGB) and HDR10 output (see
+/// [`crate::Renderer::set_output_color_space`]), so that they are shown on
+/// wide gamut displays, and are clipped to the sRGB gamut otherwise. Video
+/// textures and masks are always drawn as sRGB.
+// keep in sync with `TEXTURE_*` in `shaders/egui.hlsl`.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureColorSpace {
+    /// The primaries of sRGB and Rec. 709, as for everything `egui` draws.
+    #[default]
+    Srgb = 0,
+    /// The primaries of Display P3, e.g. of photos taken by recent phones.
+    DisplayP3 = 1,
+    /// The primaries of Rec. 2020, e.g. of frames of HDR video tone-mapped
+    /// to SDR.
+    Rec2020 = 2,
+}
+
+/// How the colors drawn with a native texture are combined with the existing
+/// content of the render target. All modes take the alpha mode of the texture
+/// into account.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Regular alpha blending, i.e. the colors are drawn over the render
+    /// target, as for everything `egui` draws.
+    #[default]
+    AlphaBlend,
+    /// The colors are added to the render target, weighted by alpha, e.g.
+    /// for glows and scanlines of HUDs. Transparent areas of the render target
+    /// are made opaque as with [`BlendMode::AlphaBlend`].
+    Additive,
+    /// The render target is multiplied by the colors, weighted by alpha, e.g.
+    /// for darkening vignettes. The alpha of the render target is kept.
+    Multiply,
+}
+
+/// How the alpha channel of a native texture is interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Colors are premultiplied by alpha, as `egui` expects.
+    #[default]
+    Premultiplied,
+    /// Colors are not premultiplied by alpha, as in most image files. Such
+    /// textures are drawn with a dedicated blend state. Consecutive draws
+    /// with the same alpha mode share the blend state, so mixing both modes
+    /// in a frame costs a state change whenever the mode changes.
+    Straight,
+}
+
+/// Receives events about the lifecycle of the textures of a
+/// [`crate::Renderer`], e.g. for instrumentation or a live dashboard of
+/// textures. Install it with [`crate::Renderer::set_texture_observer`].
+///
+/// Events are reported for textures managed by `egui` as their updates are
+/// applied, and for native textures as they are registered, updated and
+/// removed. All methods do nothing by default, so only the events of interest
+/// need to be implemented. They are called while the pool of textures is
+/// locked, so they must not call back into a [`crate::Renderer`] sharing it.
+pub trait TextureObserver: Send {
+    /// A texture of `width` by `height` pixels has been created under `id`,
+    /// replacing any texture of the same id, e.g. when the font atlas grows
+    /// or a native texture is registered again.
+    fn created(&mut self, id: TextureId, width: usize, height: usize) {
+        let _ = (id, width, height);
+    }
+
+    /// A region of `size` pixels at `pos` of the texture `id` has been
+    /// updated. Updates of native textures cover the whole new texture.
+    fn updated(&mut self, id: TextureId, pos: [usize; 2], size: [usize; 2]) {
+        let _ = (id, pos, size);
+    }
+
+    /// The texture `id` has been freed by `egui`, or a native texture has
+    /// been removed.
+    fn freed(&mut self, id: TextureId) {
+        let _ = id;
+    }
+}
+
+/// What to bind when drawing with a texture.
+pub struct TextureBinding {
+    pub srv: ID3D11ShaderResourceView,
+    pub video: Option<VideoPlanes>,
+    pub mask: bool,
+    pub sampler: SamplerOptions,
+    pub custom_sampler: Option<ID3D11SamplerState>,
+    pub alpha_mode: AlphaMode,
+    pub emissive: f32,
+    pub blend_mode: BlendMode,
+    pub color_space: TextureColorSpace,
+}
+
+impl TextureBinding {
+    /// Bind `srv` as a regular texture with premultiplied alpha.
+    pub fn new(srv: ID3D11ShaderResourceView) -> Self {
+        Self {
+            srv,
+            video: None,
+            mask: false,
+            sampler: SamplerOptions::default(),
+            custom_sampler: None,
+            alpha_mode: AlphaMode::Premultiplied,
+            emissive: 1.,
+            blend_mode: BlendMode::AlphaBlend,
+            color_space: TextureColorSpace::Srgb,
+        }
+    }
 }
 
 pub struct TexturePool {
     device: ID3D11Device,
+    // textures managed by egui, keyed by `TextureId::Managed`. besides the
+    // font atlas (id 0), egui allocates one for every image loaded through
+    // it, so nothing may assume a single managed texture.
     pool: HashMap<u64, Texture>,
-    native_pool: HashMap<u64, (ID3D11Texture2D, ID3D11ShaderResourceView)>,
+    // managed textures freed by the last update, which may still be drawn by
+    // the same frame. dropped on the next update.
+    freed: HashMap<u64, Texture>,
+    native_pool: HashMap<u64, NativeTexture>,
     next_native_idx: u64,
+    max_texture_dimension: usize,
+    default_sampler: SamplerOptions,
+    keep_cpu_mirror: bool,
+    observer: Option<Box<dyn TextureObserver>>,
 }
 
 impl TexturePool {
@@ -36,19 +244,117 @@ impl TexturePool {
         Self {
             device: device.clone(),
             pool: HashMap::new(),
+            freed: HashMap::new(),
             native_pool: HashMap::new(),
             next_native_idx: 0,
+            max_texture_dimension: Self::device_max_texture_dimension(device),
+            default_sampler: SamplerOptions::default(),
+            keep_cpu_mirror: false,
+            observer: None,
+        }
+    }
+
+    pub fn device(&self) -> &ID3D11Device {
+        &self.device
+    }
+
+    pub fn set_observer(&mut self, observer: Option<Box<dyn TextureObserver>>) {
+        self.observer = observer;
+    }
+
+    pub fn max_texture_dimension(&self) -> usize {
+        self.max_texture_dimension
+    }
+
+    pub fn set_max_texture_dimension(&mut self, dimension: usize) {
+        self.max_texture_dimension =
+            dimension.min(Self::device_max_texture_dimension(&self.device));
+    }
+
+    pub fn set_default_sampler(&mut self, sampler: SamplerOptions) {
+        self.default_sampler = sampler;
+    }
+
+    pub fn keep_cpu_mirror(&self) -> bool {
+        self.keep_cpu_mirror
+    }
+
+    /// Keep copies of the pixels of managed textures created from now on, or
+    /// drop the copies of all textures, recreating them without one.
+    pub fn set_keep_cpu_mirror(&mut self, keep: bool) -> Result<()> {
+        self.keep_cpu_mirror = keep;
+        if keep {
+            return Ok(());
+        }
+        let ids = self.pool.keys().copied().collect::<Vec<_>>();
+        for tid in ids {
+            let old = &self.pool[&tid];
+            let Some(pixels) = &old.pixels else {
+                continue;
+            };
+            // dynamic textures can not be updated partially without their
+            // copy, so they are replaced.
+            let tex = Self::create_texture_from_pixels(
+                &self.device,
+                pixels,
+                old.width,
+                old.height,
+                false,
+            )?;
+            let sampler = old.sampler;
+            // the old texture may still be drawn by commands in flight.
+            if let Some(old) = self.pool.insert(tid, Texture { sampler, ..tex })
+            {
+                self.freed.insert(tid, old);
+            }
+        }
+        Ok(())
+    }
+
+    /// Options for native textures registered without options.
+    pub fn default_native_options(&self) -> NativeTextureOptions {
+        NativeTextureOptions {
+            sampler: self.default_sampler,
+            ..Default::default()
         }
     }
 
-    pub fn get_srv(&self, tid: TextureId) -> Option<ID3D11ShaderResourceView> {
+    fn device_max_texture_dimension(device: &ID3D11Device) -> usize {
+        // D3D10_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        const FL10_REQ_TEXTURE2D_U_OR_V_DIMENSION: u32 = 8192;
+        let feature_level = unsafe { device.GetFeatureLevel() };
+        (if feature_level.0 >= D3D_FEATURE_LEVEL_11_0.0 {
+            D3D11_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        } else if feature_level.0 >= D3D_FEATURE_LEVEL_10_0.0 {
+            FL10_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        } else if feature_level == D3D_FEATURE_LEVEL_9_3 {
+            D3D_FL9_3_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        } else {
+            D3D_FL9_1_REQ_TEXTURE2D_U_OR_V_DIMENSION
+        }) as _
+    }
+
+    pub fn get(&self, tid: TextureId) -> Option<TextureBinding> {
         match tid {
             TextureId::Managed(tid) => {
-                self.pool.get(&tid).map(|t| t.srv.clone())
+                let tex = self.pool.get(&tid).or_else(|| self.freed.get(&tid));
+                tex.map(|t| TextureBinding {
+                    sampler: t.sampler,
+                    ..TextureBinding::new(t.srv.clone())
+                })
             },
             TextureId::User(tid) => {
-                let (_, shader_view) = self.native_pool.get(&tid)?;
-                Some(shader_view.clone())
+                self.native_pool.get(&tid).map(|t| TextureBinding {
+                    srv: t.srv.clone(),
+                    video: t.video.clone(),
+                    mask: t.mask,
+                    sampler: t.options.sampler,
+                    custom_sampler: t.custom_sampler.clone(),
+                    alpha_mode: t.options.alpha_mode,
+                    emissive: t.options.emissive,
+                    blend_mode: t.options.blend_mode,
+                    color_space: t.options.color_space,
+                })
             },
         }
     }
@@ -58,32 +364,84 @@ impl TexturePool {
         ctx: &ID3D11DeviceContext,
         delta: TexturesDelta,
     ) -> Result<()> {
+        // the frame which has drawn the textures freed by the last update is
+        // complete now, or has been skipped along with its output.
+        self.freed.clear();
         for (tid, delta) in
             delta.set.into_iter().filter_map(|(tid, delta)| match tid {
                 TextureId::Managed(id) => Some((id, delta)),
                 TextureId::User(_) => None,
             })
         {
+            let sampler = SamplerOptions::from(delta.options);
+            let size = delta.image.size();
             if let Some(pos) = delta.pos {
                 if let Some(tex) = self.pool.get_mut(&tid) {
-                    Self::update_partial(ctx, tex, delta.image, pos)?;
+                    tex.sampler = sampler;
+                    if Self::update_partial(
+                        &self.device,
+                        ctx,
+                        tex,
+                        delta.image,
+                        pos,
+                    )? && let Some(observer) = &mut self.observer
+                    {
+                        observer.updated(TextureId::Managed(tid), pos, size);
+                    }
                 } else {
                     log::warn!(
                         "egui wants to update a non-existing texture {tid:?}. this request will be ignored."
                     );
                 }
+            } else if let Some(tex) = self.pool.get_mut(&tid).filter(|tex| {
+                (tex.width, tex.height)
+                    == (delta.image.width(), delta.image.height())
+            }) {
+                // the image is resent as a whole, e.g. because only its
+                // options have changed. reuse the texture as it fits.
+                tex.sampler = sampler;
+                Self::update_partial(
+                    &self.device,
+                    ctx,
+                    tex,
+                    delta.image,
+                    [0, 0],
+                )?;
+                if let Some(observer) = &mut self.observer {
+                    observer.updated(TextureId::Managed(tid), [0, 0], size);
+                }
             } else {
                 if delta.image.width() > 0 && delta.image.height() > 0 {
-                    self.pool.insert(
-                        tid,
-                        Self::create_texture(&self.device, delta.image)?,
-                    );
+                    // the sampler is looked up by its options on every draw,
+                    // so the options of the new image take effect as is.
+                    let mut tex = self.create_texture(delta.image)?;
+                    tex.sampler = sampler;
+                    // the replaced texture of a different size may still be
+                    // referenced by draws in flight, so release it along with
+                    // the freed textures.
+                    if let Some(old) = self.pool.insert(tid, tex) {
+                        self.freed.insert(tid, old);
+                    }
+                    if let Some(observer) = &mut self.observer {
+                        observer.created(
+                            TextureId::Managed(tid),
+                            size[0],
+                            size[1],
+                        );
+                    }
                 }
             }
         }
+        // `egui` frees textures along with the output of the frame which
+        // drew them last, so keep them until the next update.
         for tid in delta.free {
-            if let TextureId::Managed(tid) = tid {
-                self.pool.remove(&tid);
+            if let TextureId::Managed(id) = tid
+                && let Some(tex) = self.pool.remove(&id)
+            {
+                self.freed.insert(id, tex);
+                if let Some(observer) = &mut self.observer {
+                    observer.freed(tid);
+                }
             }
         }
         Ok(())
@@ -92,19 +450,493 @@ impl TexturePool {
     pub fn register_native_texture(
         &mut self,
         texture: ID3D11Texture2D,
+        options: NativeTextureOptions,
+    ) -> Result<TextureId> {
+        if let Some(id) = self.find_native_texture(&texture) {
+            if let Some(tex) = self.native_pool.get_mut(&id) {
+                tex.options = options;
+            }
+            return Ok(TextureId::User(id));
+        }
+        let srv = self.create_default_srv(&texture)?;
+        Ok(self.register_native_texture_with_srv(texture, srv, options))
+    }
+
+    /// Create a view of the texture in its own format, or in a typed format
+    /// of the same family if its format is typeless.
+    fn create_default_srv(
+        &self,
+        texture: &ID3D11Texture2D,
+    ) -> Result<ID3D11ShaderResourceView> {
+        let mut srv = None;
+        let err = match unsafe {
+            self.device
+                .CreateShaderResourceView(texture, None, Some(&mut srv))
+        } {
+            Ok(()) => return Ok(srv.unwrap()),
+            Err(err) => err,
+        };
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        let Some(format) = typed_from_typeless(desc.Format) else {
+            return Err(Error::new(
+                err.code(),
+                format!(
+                    "failed to view a texture of format {}: {}",
+                    desc.Format.0,
+                    err.message()
+                ),
+            ));
+        };
+        let srv = self.create_srv(texture, format).map_err(|fallback_err| {
+            Error::new(
+                err.code(),
+                format!(
+                    "failed to view a texture of format {}, also as format {}: {}",
+                    desc.Format.0,
+                    format.0,
+                    fallback_err.message()
+                ),
+            )
+        })?;
+        log::debug!(
+            "viewing a native texture of typeless format {} as format {}.",
+            desc.Format.0,
+            format.0
+        );
+        Ok(srv)
+    }
+
+    pub fn register_native_texture_with_srv(
+        &mut self,
+        texture: ID3D11Texture2D,
+        srv: ID3D11ShaderResourceView,
+        options: NativeTextureOptions,
     ) -> TextureId {
-        let id = self.next_native_idx;
-        self.next_native_idx += 1;
+        self.insert_native_texture(texture, srv, None, options)
+    }
+
+    pub fn register_rgba8(
+        &mut self,
+        width: usize,
+        height: usize,
+        rgba: &[u8],
+    ) -> Result<TextureId> {
+        // checked first, so that the size of the image can not overflow.
+        if width == 0
+            || height == 0
+            || width > self.max_texture_dimension
+            || height > self.max_texture_dimension
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "texture size {width}x{height} is empty or exceeds the maximum texture dimension {}",
+                    self.max_texture_dimension
+                ),
+            ));
+        }
+        if rgba.len() != width * height * 4 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "expected {} bytes for a {width}x{height} RGBA8 image, got {}",
+                    width * height * 4,
+                    rgba.len()
+                ),
+            ));
+        }
+
+        let pixels = rgba
+            .chunks_exact(4)
+            .map(|p| Color32::from_rgba_unmultiplied(p[0], p[1], p[2], p[3]))
+            .collect::<Vec<_>>();
+        let desc = D3D11_TEXTURE2D_DESC {
+            Width: width as _,
+            Height: height as _,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Usage: D3D11_USAGE_IMMUTABLE,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
+            ..Default::default()
+        };
+        let subresource_data = D3D11_SUBRESOURCE_DATA {
+            pSysMem: pixels.as_ptr() as _,
+            // rows are tightly packed as collected above.
+            SysMemPitch: (width * mem::size_of::<Color32>()) as _,
+            SysMemSlicePitch: 0,
+        };
+        let mut tex = None;
+        unsafe {
+            self.device.CreateTexture2D(
+                &desc,
+                Some(&subresource_data),
+                Some(&mut tex),
+            )
+        }?;
+        let tex = tex.unwrap();
         let mut srv = None;
         unsafe {
             self.device
-                .CreateShaderResourceView(&texture, None, Some(&mut srv))
+                .CreateShaderResourceView(&tex, None, Some(&mut srv))
+        }?;
+        let options = self.default_native_options();
+        Ok(self.insert_native_texture(tex, srv.unwrap(), None, options))
+    }
+
+    pub fn register_from_staging(
+        &mut self,
+        ctx: &ID3D11DeviceContext,
+        staging: &ID3D11Texture2D,
+    ) -> Result<TextureId> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { staging.GetDesc(&mut desc) };
+        if desc.Usage != D3D11_USAGE_STAGING {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "the texture is not a staging texture",
+            ));
         }
-        .unwrap();
-        self.native_pool.insert(id, (texture, srv.unwrap()));
+        if desc.ArraySize != 1 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "expected a texture with 1 array slice, got {}",
+                    desc.ArraySize
+                ),
+            ));
+        }
+        if desc.Width as usize > self.max_texture_dimension
+            || desc.Height as usize > self.max_texture_dimension
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "texture size {}x{} exceeds the maximum texture dimension {}",
+                    desc.Width, desc.Height, self.max_texture_dimension
+                ),
+            ));
+        }
+
+        let desc = D3D11_TEXTURE2D_DESC {
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
+            CPUAccessFlags: 0,
+            MiscFlags: 0,
+            ..desc
+        };
+        let mut tex = None;
+        unsafe { self.device.CreateTexture2D(&desc, None, Some(&mut tex)) }
+            .map_err(|err| {
+                Error::new(
+                    err.code(),
+                    format!(
+                        "failed to create a texture of format {} for sampling: {}",
+                        desc.Format.0,
+                        err.message()
+                    ),
+                )
+            })?;
+        let tex = tex.unwrap();
+        let mut srv = None;
+        unsafe {
+            self.device
+                .CreateShaderResourceView(&tex, None, Some(&mut srv))?;
+            ctx.CopyResource(&tex, staging);
+        }
+        let options = self.default_native_options();
+        Ok(self.insert_native_texture(tex, srv.unwrap(), None, options))
+    }
+
+    pub fn register_video_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+        format: VideoFormat,
+    ) -> Result<TextureId> {
+        let (luma_srv, video) = self.create_video_views(&texture, format)?;
+        let options = self.default_native_options();
+        Ok(self.insert_native_texture(texture, luma_srv, Some(video), options))
+    }
+
+    pub fn register_mask_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+    ) -> Result<TextureId> {
+        Self::check_mask_format(&texture)?;
+        let srv = self.create_default_srv(&texture)?;
+        let options = self.default_native_options();
+        let tid = self.insert_native_texture(texture, srv, None, options);
+        self.native_texture_mut(tid)?.mask = true;
+        Ok(tid)
+    }
+
+    fn check_mask_format(texture: &ID3D11Texture2D) -> Result<()> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        match desc.Format {
+            DXGI_FORMAT_R8_UNORM
+            | DXGI_FORMAT_R8_TYPELESS
+            | DXGI_FORMAT_R16_UNORM
+            | DXGI_FORMAT_R16_FLOAT
+            | DXGI_FORMAT_R16_TYPELESS
+            | DXGI_FORMAT_R32_FLOAT
+            | DXGI_FORMAT_R32_TYPELESS => Ok(()),
+            format => Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "expected a texture of a single channel format for a mask, got format {}",
+                    format.0
+                ),
+            )),
+        }
+    }
+
+    fn create_video_views(
+        &self,
+        texture: &ID3D11Texture2D,
+        format: VideoFormat,
+    ) -> Result<(ID3D11ShaderResourceView, VideoPlanes)> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { texture.GetDesc(&mut desc) };
+        let (expected_format, luma_format, chroma_format) = match format {
+            VideoFormat::Nv12 => (
+                DXGI_FORMAT_NV12,
+                DXGI_FORMAT_R8_UNORM,
+                DXGI_FORMAT_R8G8_UNORM,
+            ),
+        };
+        if desc.Format != expected_format || desc.ArraySize != 1 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "expected a single {format:?} texture, got {} slice(s) of format {}",
+                    desc.ArraySize, desc.Format.0
+                ),
+            ));
+        }
+
+        let luma_srv = self.create_srv(texture, luma_format)?;
+        let chroma_srv = self.create_srv(texture, chroma_format)?;
+        Ok((luma_srv, VideoPlanes { format, chroma_srv }))
+    }
+
+    pub fn set_native_texture_options(
+        &mut self,
+        tid: TextureId,
+        options: egui::TextureOptions,
+    ) -> Result<()> {
+        let sampler = &mut self.native_texture_mut(tid)?.options.sampler;
+        // keep the LOD and anisotropy, which egui does not know about.
+        *sampler = SamplerOptions {
+            mip_lod_bias: sampler.mip_lod_bias,
+            min_lod: sampler.min_lod,
+            max_lod: sampler.max_lod,
+            max_anisotropy: sampler.max_anisotropy,
+            ..options.into()
+        };
+        Ok(())
+    }
+
+    pub fn set_native_texture_blend_mode(
+        &mut self,
+        tid: TextureId,
+        blend_mode: BlendMode,
+    ) -> Result<()> {
+        self.native_texture_mut(tid)?.options.blend_mode = blend_mode;
+        Ok(())
+    }
+
+    pub fn set_native_texture_sampler(
+        &mut self,
+        tid: TextureId,
+        sampler: Option<ID3D11SamplerState>,
+    ) -> Result<()> {
+        if let Some(sampler) = &sampler {
+            let device = unsafe { sampler.GetDevice() }?;
+            if device.as_raw() != self.device.as_raw() {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    "the sampler has been created on a different device",
+                ));
+            }
+        }
+        self.native_texture_mut(tid)?.custom_sampler = sampler;
+        Ok(())
+    }
+
+    fn native_texture_mut(
+        &mut self,
+        tid: TextureId,
+    ) -> Result<&mut NativeTexture> {
+        match tid {
+            TextureId::User(id) => {
+                self.native_pool.get_mut(&id).ok_or_else(|| {
+                    Error::new(
+                        E_INVALIDARG,
+                        format!("{tid:?} is not a registered native texture"),
+                    )
+                })
+            },
+            TextureId::Managed(_) => Err(Error::new(
+                E_INVALIDARG,
+                "options of managed textures are set by egui",
+            )),
+        }
+    }
+
+    pub fn update_native_texture(
+        &mut self,
+        tid: TextureId,
+        texture: ID3D11Texture2D,
+    ) -> Result<()> {
+        let id = match tid {
+            TextureId::User(id) if self.native_pool.contains_key(&id) => id,
+            _ => {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    format!("{tid:?} is not a registered native texture"),
+                ));
+            },
+        };
+        let old = &self.native_pool[&id];
+
+        if old.mask {
+            Self::check_mask_format(&texture)?;
+        }
+        let (srv, video) = if let Some(video) = &old.video {
+            let (srv, video) =
+                self.create_video_views(&texture, video.format)?;
+            (srv, Some(video))
+        } else {
+            // keep viewing the texture in the format of the old view, in case
+            // it has been registered with a specific format.
+            let mut old_desc = D3D11_TEXTURE2D_DESC::default();
+            let mut old_view_desc = D3D11_SHADER_RESOURCE_VIEW_DESC::default();
+            unsafe {
+                old.tex.GetDesc(&mut old_desc);
+                old.srv.GetDesc(&mut old_view_desc);
+            }
+            let srv = if old_view_desc.Format == old_desc.Format {
+                let mut srv = None;
+                unsafe {
+                    self.device.CreateShaderResourceView(
+                        &texture,
+                        None,
+                        Some(&mut srv),
+                    )
+                }?;
+                srv.unwrap()
+            } else {
+                self.create_srv(&texture, old_view_desc.Format)?
+            };
+            (srv, None)
+        };
+
+        if let Some(observer) = &mut self.observer {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe { texture.GetDesc(&mut desc) };
+            let size = [desc.Width as usize, desc.Height as usize];
+            observer.updated(tid, [0, 0], size);
+        }
+        let entry = self.native_pool.get_mut(&id).unwrap();
+        entry.tex = texture;
+        entry.srv = srv;
+        entry.video = video;
+        Ok(())
+    }
+
+    pub fn register_native_texture_with_format(
+        &mut self,
+        texture: ID3D11Texture2D,
+        format: DXGI_FORMAT,
+        options: NativeTextureOptions,
+    ) -> Result<TextureId> {
+        let srv = self.create_srv(&texture, format).map_err(|err| {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe { texture.GetDesc(&mut desc) };
+            Error::new(
+                err.code(),
+                format!(
+                    "failed to view a texture of format {} as format {}: {}",
+                    desc.Format.0,
+                    format.0,
+                    err.message()
+                ),
+            )
+        })?;
+        Ok(self.insert_native_texture(texture, srv, None, options))
+    }
+
+    fn create_srv(
+        &self,
+        texture: &ID3D11Texture2D,
+        format: DXGI_FORMAT,
+    ) -> Result<ID3D11ShaderResourceView> {
+        let desc = D3D11_SHADER_RESOURCE_VIEW_DESC {
+            Format: format,
+            ViewDimension: D3D11_SRV_DIMENSION_TEXTURE2D,
+            Anonymous: D3D11_SHADER_RESOURCE_VIEW_DESC_0 {
+                Texture2D: D3D11_TEX2D_SRV {
+                    MostDetailedMip: 0,
+                    MipLevels: u32::MAX,
+                },
+            },
+        };
+        let mut srv = None;
+        unsafe {
+            self.device.CreateShaderResourceView(
+                texture,
+                Some(&desc),
+                Some(&mut srv),
+            )
+        }?;
+        Ok(srv.unwrap())
+    }
+
+    fn insert_native_texture(
+        &mut self,
+        texture: ID3D11Texture2D,
+        srv: ID3D11ShaderResourceView,
+        video: Option<VideoPlanes>,
+        options: NativeTextureOptions,
+    ) -> TextureId {
+        let id = self.find_native_texture(&texture).unwrap_or_else(|| {
+            let id = self.next_native_idx;
+            self.next_native_idx += 1;
+            id
+        });
+        if let Some(observer) = &mut self.observer {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            unsafe { texture.GetDesc(&mut desc) };
+            let (width, height) = (desc.Width as usize, desc.Height as usize);
+            observer.created(TextureId::User(id), width, height);
+        }
+        self.native_pool.insert(
+            id,
+            NativeTexture {
+                tex: texture,
+                srv,
+                video,
+                mask: false,
+                options,
+                custom_sampler: None,
+            },
+        );
         TextureId::User(id)
     }
 
+    fn find_native_texture(&self, texture: &ID3D11Texture2D) -> Option<u64> {
+        self.native_pool
+            .iter()
+            .find(|(_, tex)| tex.tex.as_raw() == texture.as_raw())
+            .map(|(id, _)| *id)
+    }
+
     pub fn remove_native_texture(
         &mut self,
         tid: &TextureId,
@@ -113,70 +945,455 @@ impl TexturePool {
             TextureId::Managed(_) => {
                 panic!("Cannot manually remove managed textures")
             },
-            TextureId::User(tid) => {
-                self.native_pool.remove(tid).map(|(tex, _)| tex)
+            TextureId::User(id) => {
+                let tex = self.native_pool.remove(id)?;
+                if let Some(observer) = &mut self.observer {
+                    observer.freed(*tid);
+                }
+                Some(tex.tex)
             },
         }
     }
 
+    pub fn contains(&self, tid: TextureId) -> bool {
+        match tid {
+            TextureId::Managed(tid) => self.pool.contains_key(&tid),
+            TextureId::User(tid) => self.native_pool.contains_key(&tid),
+        }
+    }
+
+    pub fn native_texture_ids(&self) -> Vec<TextureId> {
+        let mut ids = self.native_pool.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids.into_iter().map(TextureId::User).collect()
+    }
+
+    pub fn retain_native_textures(
+        &mut self,
+        mut f: impl FnMut(TextureId) -> bool,
+    ) -> usize {
+        let count = self.native_pool.len();
+        let observer = &mut self.observer;
+        self.native_pool.retain(|tid, _| {
+            let retain = f(TextureId::User(*tid));
+            if !retain && let Some(observer) = observer {
+                observer.freed(TextureId::User(*tid));
+            }
+            retain
+        });
+        count - self.native_pool.len()
+    }
+
+    #[cfg(feature = "test-util")]
+    pub fn clear_freed_textures(&mut self) {
+        self.freed.clear();
+    }
+
+    pub fn clear_native_textures(&mut self) -> usize {
+        let count = self.native_pool.len();
+        for (tid, _) in self.native_pool.drain() {
+            if let Some(observer) = &mut self.observer {
+                observer.freed(TextureId::User(tid));
+            }
+        }
+        count
+    }
+
+    pub fn reupload_managed_textures(&mut self) -> Result<()> {
+        if self.pool.values().any(|tex| tex.pixels.is_none()) {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "the pixels of managed textures are not kept, see `Renderer::set_keep_cpu_mirror`",
+            ));
+        }
+        // create all textures before replacing any, so that the pool is left
+        // as it is if one of them fails.
+        let textures = self
+            .pool
+            .iter()
+            .map(|(&tid, old)| {
+                let mut tex = Self::create_texture_from_pixels(
+                    &self.device,
+                    old.pixels.as_deref().unwrap_or_default(),
+                    old.width,
+                    old.height,
+                    true,
+                )?;
+                tex.sampler = old.sampler;
+                Ok((tid, tex))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // the replaced textures may still be referenced by draws in flight.
+        for (tid, tex) in textures {
+            if let Some(old) = self.pool.insert(tid, tex) {
+                self.freed.insert(tid, old);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn export_managed_textures(&self) -> Vec<u8> {
+        let mut ids = self
+            .pool
+            .iter()
+            .filter(|(_, tex)| tex.pixels.is_some())
+            .map(|(tid, _)| *tid)
+            .collect::<Vec<_>>();
+        if ids.len() < self.pool.len() {
+            log::warn!(concat!(
+                "the pixels of some managed textures are not kept, see ",
+                "`Renderer::set_keep_cpu_mirror`. they will not be exported."
+            ));
+        }
+        ids.sort_unstable();
+        let mut blob = Vec::new();
+        blob.extend_from_slice(TEXTURE_CACHE_MAGIC);
+        blob.extend_from_slice(&TEXTURE_CACHE_VERSION.to_le_bytes());
+        blob.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+        for tid in ids {
+            let tex = &self.pool[&tid];
+            blob.extend_from_slice(&tid.to_le_bytes());
+            blob.extend_from_slice(&(tex.width as u32).to_le_bytes());
+            blob.extend_from_slice(&(tex.height as u32).to_le_bytes());
+            let filter = |filter| match filter {
+                TextureFilter::Nearest => 0u8,
+                TextureFilter::Linear => 1,
+            };
+            blob.push(filter(tex.sampler.magnification));
+            blob.push(filter(tex.sampler.minification));
+            let (address, color) = match tex.sampler.address_mode {
+                AddressMode::Clamp => (0u8, [0.; 4]),
+                AddressMode::Repeat => (1, [0.; 4]),
+                AddressMode::Mirror => (2, [0.; 4]),
+                AddressMode::ClampToBorder { color } => (3, color),
+            };
+            blob.push(address);
+            for c in color {
+                blob.extend_from_slice(&c.to_le_bytes());
+            }
+            blob.extend(tex.pixels.iter().flatten().flat_map(|p| p.to_array()));
+        }
+        blob
+    }
+
+    pub fn import_managed_textures(&mut self, blob: &[u8]) -> Result<usize> {
+        let invalid =
+            || Error::new(E_INVALIDARG, "the texture cache blob is malformed");
+        let mut reader = BlobReader(blob);
+        if reader.take(TEXTURE_CACHE_MAGIC.len()).ok_or_else(invalid)?
+            != TEXTURE_CACHE_MAGIC
+        {
+            return Err(invalid());
+        }
+        let version = reader.u32().ok_or_else(invalid)?;
+        if version != TEXTURE_CACHE_VERSION {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "the texture cache blob has version {version}, while version {TEXTURE_CACHE_VERSION} is supported"
+                ),
+            ));
+        }
+        // parse everything before creating any texture, so that a malformed
+        // blob leaves the pool untouched.
+        let count = reader.u32().ok_or_else(invalid)?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let tid = reader.u64().ok_or_else(invalid)?;
+            let width = reader.u32().ok_or_else(invalid)? as usize;
+            let height = reader.u32().ok_or_else(invalid)? as usize;
+            let filter = |filter| match filter {
+                0 => Some(TextureFilter::Nearest),
+                1 => Some(TextureFilter::Linear),
+                _ => None,
+            };
+            let magnification =
+                filter(reader.u8().ok_or_else(invalid)?).ok_or_else(invalid)?;
+            let minification =
+                filter(reader.u8().ok_or_else(invalid)?).ok_or_else(invalid)?;
+            let address = reader.u8().ok_or_else(invalid)?;
+            let mut color = [0.; 4];
+            for c in &mut color {
+                *c = f32::from_le_bytes(
+                    reader.take(4).ok_or_else(invalid)?.try_into().unwrap(),
+                );
+            }
+            let address_mode = match address {
+                0 => AddressMode::Clamp,
+                1 => AddressMode::Repeat,
+                2 => AddressMode::Mirror,
+                3 => AddressMode::ClampToBorder { color },
+                _ => return Err(invalid()),
+            };
+            let len = width
+                .checked_mul(height)
+                .and_then(|len| len.checked_mul(mem::size_of::<Color32>()))
+                .ok_or_else(invalid)?;
+            let pixels = reader
+                .take(len)
+                .ok_or_else(invalid)?
+                .chunks_exact(4)
+                .map(|p| {
+                    Color32::from_rgba_premultiplied(p[0], p[1], p[2], p[3])
+                })
+                .collect::<Vec<_>>();
+            let sampler = SamplerOptions {
+                magnification,
+                minification,
+                address_mode,
+                ..SamplerOptions::default()
+            };
+            entries.push((tid, pixels, width, height, sampler));
+        }
+        if !reader.0.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut count = 0;
+        for (tid, pixels, width, height, sampler) in entries {
+            if width == 0 || height == 0 {
+                continue;
+            }
+            if width > self.max_texture_dimension
+                || height > self.max_texture_dimension
+            {
+                return Err(Error::new(
+                    E_INVALIDARG,
+                    format!(
+                        "texture size {width}x{height} exceeds the maximum texture dimension {}",
+                        self.max_texture_dimension
+                    ),
+                ));
+            }
+            let mut tex = Self::create_texture_from_pixels(
+                &self.device,
+                &pixels,
+                width,
+                height,
+                self.keep_cpu_mirror,
+            )?;
+            tex.sampler = sampler;
+            // the replaced texture may still be drawn by commands in flight.
+            if let Some(old) = self.pool.insert(tid, tex) {
+                self.freed.insert(tid, old);
+            }
+            count += 1;
+            if let Some(observer) = &mut self.observer {
+                observer.created(TextureId::Managed(tid), width, height);
+            }
+        }
+        Ok(count)
+    }
+
     fn update_partial(
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+        old: &mut Texture,
+        image: ImageData,
+        pos: [usize; 2],
+    ) -> Result<bool> {
+        Self::update_partial_with(device, ctx, old, image, pos, Self::upload)
+    }
+
+    /// [`Self::update_partial`], uploading textures with a copy of their
+    /// pixels with `upload`.
+    fn update_partial_with(
+        device: &ID3D11Device,
         ctx: &ID3D11DeviceContext,
         old: &mut Texture,
         image: ImageData,
         [nx, ny]: [usize; 2],
-    ) -> Result<()> {
-        let subr = unsafe {
-            let mut output = D3D11_MAPPED_SUBRESOURCE::default();
-            ctx.Map(
-                &old.tex,
-                0,
-                D3D11_MAP_WRITE_DISCARD,
-                0,
-                Some(&mut output),
-            )?;
-            output
+        upload: impl FnOnce(&ID3D11DeviceContext, &Texture) -> Result<()>,
+    ) -> Result<bool> {
+        let in_bounds = nx
+            .checked_add(image.width())
+            .is_some_and(|right| right <= old.width)
+            && ny
+                .checked_add(image.height())
+                .is_some_and(|bottom| bottom <= old.height);
+        if !in_bounds {
+            log::warn!(
+                concat!(
+                    "egui wants to update a {}x{} region at {:?} of a {}x{} ",
+                    "texture, which is out of bounds. ",
+                    "this request will be ignored."
+                ),
+                image.width(),
+                image.height(),
+                [nx, ny],
+                old.width,
+                old.height,
+            );
+            return Ok(false);
+        }
+
+        let Some(pixels) = &mut old.pixels else {
+            match image {
+                ImageData::Color(f) => {
+                    Self::update_region(ctx, old, &f, [nx, ny])
+                },
+            }
+            return Ok(true);
         };
         match image {
             ImageData::Color(f) => {
-                let data = unsafe {
-                    let slice = slice::from_raw_parts_mut(
-                        subr.pData as *mut Color32,
-                        old.pixels.len(),
-                    );
-                    slice.as_mut_ptr().copy_from_nonoverlapping(
-                        old.pixels.as_ptr(),
-                        old.pixels.len(),
-                    );
-                    slice
-                };
-
                 for y in 0..f.height() {
                     for x in 0..f.width() {
                         let whole = (ny + y) * old.width + nx + x;
                         let frac = y * f.width() + x;
-                        old.pixels[whole] = f.pixels[frac];
-                        data[whole] = f.pixels[frac];
+                        pixels[whole] = f.pixels[frac];
                     }
                 }
             },
         }
-        unsafe { ctx.Unmap(&old.tex, 0) };
+
+        let Err(err) = upload(ctx, old) else {
+            return Ok(true);
+        };
+        if unsafe { device.GetDeviceRemovedReason() }.is_err() {
+            return Err(Error::new(
+                err.code(),
+                format!(
+                    "the device has been removed while updating a texture: {}",
+                    err.message()
+                ),
+            ));
+        }
+        if err.code() != E_INVALIDARG && err.code() != DXGI_ERROR_INVALID_CALL {
+            return Err(err);
+        }
+        // the texture can not be mapped, e.g. on some deferred contexts. as the
+        // whole content is kept in `pixels`, recreate the texture without a
+        // copy, so that it is updated with `UpdateSubresource` from now on
+        // instead of failing to map it again.
+        log::warn!(
+            concat!(
+                "failed to map a texture for updating ({}). it will be ",
+                "recreated without a copy of its pixels, and will not be ",
+                "exported."
+            ),
+            err
+        );
+        let tex = Self::create_texture_from_pixels(
+            device,
+            old.pixels.as_deref().unwrap_or_default(),
+            old.width,
+            old.height,
+            false,
+        )?;
+        *old = Texture {
+            sampler: old.sampler,
+            ..tex
+        };
+        Ok(true)
+    }
+
+    fn upload(ctx: &ID3D11DeviceContext, tex: &Texture) -> Result<()> {
+        let Some(pixels) = &tex.pixels else {
+            return Ok(());
+        };
+        unsafe {
+            let mut subr = D3D11_MAPPED_SUBRESOURCE::default();
+            ctx.Map(&tex.tex, 0, D3D11_MAP_WRITE_DISCARD, 0, Some(&mut subr))?;
+            // rows of the mapped texture may be padded by the driver, e.g.
+            // to 64 or 256 bytes, so copy them one by one.
+            let data = subr.pData as *mut u8;
+            for (y, row) in pixels.chunks_exact(tex.width).enumerate() {
+                slice::from_raw_parts_mut(
+                    data.add(y * subr.RowPitch as usize) as *mut Color32,
+                    row.len(),
+                )
+                .copy_from_slice(row);
+            }
+            ctx.Unmap(&tex.tex, 0);
+        }
         Ok(())
     }
 
-    fn create_texture(
-        device: &ID3D11Device,
-        data: ImageData,
-    ) -> Result<Texture> {
+    /// Write `image` at `pos` of a texture without a copy of its pixels.
+    fn update_region(
+        ctx: &ID3D11DeviceContext,
+        tex: &Texture,
+        image: &ColorImage,
+        [x, y]: [usize; 2],
+    ) {
+        let region = D3D11_BOX {
+            left: x as _,
+            top: y as _,
+            front: 0,
+            right: (x + image.width()) as _,
+            bottom: (y + image.height()) as _,
+            back: 1,
+        };
+        unsafe {
+            ctx.UpdateSubresource(
+                &tex.tex,
+                0,
+                Some(&region),
+                image.pixels.as_ptr() as _,
+                // `ColorImage` packs rows tightly.
+                (image.width() * mem::size_of::<Color32>()) as _,
+                0,
+            );
+        }
+    }
+
+    fn create_texture(&self, data: ImageData) -> Result<Texture> {
         let width = data.width();
+        let height = data.height();
+        if width > self.max_texture_dimension
+            || height > self.max_texture_dimension
+        {
+            log::error!(
+                concat!(
+                    "egui wants to create a {}x{} texture, while at most {} ",
+                    "pixels in each dimension are supported. if this is the ",
+                    "font atlas, consider reducing font sizes or passing ",
+                    "`Renderer::max_texture_dimension` to egui."
+                ),
+                width,
+                height,
+                self.max_texture_dimension,
+            );
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "texture size {width}x{height} exceeds the maximum texture dimension {}",
+                    self.max_texture_dimension
+                ),
+            ));
+        }
 
         let pixels = match &data {
-            ImageData::Color(c) => c.pixels.clone(),
+            ImageData::Color(c) => &c.pixels,
         };
+        Self::create_texture_from_pixels(
+            &self.device,
+            pixels,
+            width,
+            height,
+            self.keep_cpu_mirror,
+        )
+    }
 
+    /// Create a texture with the content `pixels`. Textures keeping a copy
+    /// are updated by mapping them as a whole, and others with
+    /// `UpdateSubresource`.
+    fn create_texture_from_pixels(
+        device: &ID3D11Device,
+        pixels: &[Color32],
+        width: usize,
+        height: usize,
+        keep_cpu_mirror: bool,
+    ) -> Result<Texture> {
+        let (usage, cpu_access) = if keep_cpu_mirror {
+            (D3D11_USAGE_DYNAMIC, D3D11_CPU_ACCESS_WRITE.0 as _)
+        } else {
+            (D3D11_USAGE_DEFAULT, 0)
+        };
         let desc = D3D11_TEXTURE2D_DESC {
-            Width: data.width() as _,
-            Height: data.height() as _,
+            Width: width as _,
+            Height: height as _,
             MipLevels: 1,
             ArraySize: 1,
             Format: DXGI_FORMAT_R8G8B8A8_UNORM,
@@ -184,15 +1401,18 @@ impl TexturePool {
                 Count: 1,
                 Quality: 0,
             },
-            Usage: D3D11_USAGE_DYNAMIC,
+            Usage: usage,
             BindFlags: D3D11_BIND_SHADER_RESOURCE.0 as _,
-            CPUAccessFlags: D3D11_CPU_ACCESS_WRITE.0 as _,
+            CPUAccessFlags: cpu_access,
             ..Default::default()
         };
 
+        // `egui` and the copies of this crate pack the rows of `pixels`
+        // tightly, i.e. without padding, so a row is `width` pixels long.
+        debug_assert_eq!(pixels.len(), width * height);
         let subresource_data = D3D11_SUBRESOURCE_DATA {
             pSysMem: pixels.as_ptr() as _,
-            SysMemPitch: (width * mem::size_of::<Color32>()) as u32,
+            SysMemPitch: (width * mem::size_of::<Color32>()) as _,
             SysMemSlicePitch: 0,
         };
 
@@ -214,7 +1434,372 @@ impl TexturePool {
             tex,
             srv,
             width,
-            pixels,
+            height,
+            pixels: keep_cpu_mirror.then(|| pixels.to_vec()),
+            sampler: SamplerOptions::default(),
         })
     }
 }
+
+/// A best-effort typed format for sampling textures of a typeless format, as
+/// often used by shared surfaces.
+fn typed_from_typeless(format: DXGI_FORMAT) -> Option<DXGI_FORMAT> {
+    Some(match format {
+        DXGI_FORMAT_R8G8B8A8_TYPELESS => DXGI_FORMAT_R8G8B8A8_UNORM,
+        DXGI_FORMAT_B8G8R8A8_TYPELESS => DXGI_FORMAT_B8G8R8A8_UNORM,
+        DXGI_FORMAT_B8G8R8X8_TYPELESS => DXGI_FORMAT_B8G8R8X8_UNORM,
+        DXGI_FORMAT_R10G10B10A2_TYPELESS => DXGI_FORMAT_R10G10B10A2_UNORM,
+        DXGI_FORMAT_R16G16B16A16_TYPELESS => DXGI_FORMAT_R16G16B16A16_FLOAT,
+        DXGI_FORMAT_R32G32B32A32_TYPELESS => DXGI_FORMAT_R32G32B32A32_FLOAT,
+        DXGI_FORMAT_R8_TYPELESS => DXGI_FORMAT_R8_UNORM,
+        DXGI_FORMAT_R8G8_TYPELESS => DXGI_FORMAT_R8G8_UNORM,
+        DXGI_FORMAT_R16_TYPELESS => DXGI_FORMAT_R16_UNORM,
+        DXGI_FORMAT_R32_TYPELESS => DXGI_FORMAT_R32_FLOAT,
+        DXGI_FORMAT_BC1_TYPELESS => DXGI_FORMAT_BC1_UNORM,
+        DXGI_FORMAT_BC2_TYPELESS => DXGI_FORMAT_BC2_UNORM,
+        DXGI_FORMAT_BC3_TYPELESS => DXGI_FORMAT_BC3_UNORM,
+        DXGI_FORMAT_BC4_TYPELESS => DXGI_FORMAT_BC4_UNORM,
+        DXGI_FORMAT_BC5_TYPELESS => DXGI_FORMAT_BC5_UNORM,
+        DXGI_FORMAT_BC6H_TYPELESS => DXGI_FORMAT_BC6H_UF16,
+        DXGI_FORMAT_BC7_TYPELESS => DXGI_FORMAT_BC7_UNORM,
+        _ => return None,
+    })
+}
+
+const TEXTURE_CACHE_MAGIC: &[u8] = b"EGUIDX11";
+const TEXTURE_CACHE_VERSION: u32 = 1;
+
+struct BlobReader<'a>(&'a [u8]);
+
+impl<'a> BlobReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8)
+            .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+}
+
+#[cfg(all(test, feature = "test-util"))]
+pub(crate) mod tests {
+    use super::*;
+    use crate::Renderer;
+    use egui::{TextureOptions, epaint::ImageDelta};
+    use windows::Win32::Foundation::E_FAIL;
+
+    fn pool() -> (ID3D11DeviceContext, TexturePool) {
+        let (device, ctx, _) = Renderer::new_warp().unwrap();
+        (ctx, TexturePool::new(&device))
+    }
+
+    fn image(width: usize, height: usize, color: Color32) -> ImageData {
+        ImageData::Color(
+            ColorImage::new([width, height], vec![color; width * height])
+                .into(),
+        )
+    }
+
+    fn set(
+        pool: &mut TexturePool,
+        ctx: &ID3D11DeviceContext,
+        pos: Option<[usize; 2]>,
+        image: ImageData,
+    ) {
+        let delta = match pos {
+            Some(pos) => {
+                ImageDelta::partial(pos, image, TextureOptions::LINEAR)
+            },
+            None => ImageDelta::full(image, TextureOptions::LINEAR),
+        };
+        let delta = TexturesDelta {
+            set: vec![(TextureId::Managed(0), delta)],
+            free: Vec::new(),
+        };
+        pool.update(ctx, delta).unwrap();
+    }
+
+    /// Copy `tex` to a staging texture and read its pixels back.
+    pub(crate) fn read_back(
+        ctx: &ID3D11DeviceContext,
+        tex: &ID3D11Texture2D,
+    ) -> Vec<Color32> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { tex.GetDesc(&mut desc) };
+        desc.Usage = D3D11_USAGE_STAGING;
+        desc.BindFlags = 0;
+        desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ.0 as _;
+        desc.MiscFlags = 0;
+        let device = unsafe { ctx.GetDevice() }.unwrap();
+        let mut staging = None;
+        unsafe { device.CreateTexture2D(&desc, None, Some(&mut staging)) }
+            .unwrap();
+        let staging = staging.unwrap();
+        let (width, height) = (desc.Width as usize, desc.Height as usize);
+        let mut pixels = Vec::with_capacity(width * height);
+        unsafe {
+            ctx.CopyResource(&staging, tex);
+            let mut mapped = D3D11_MAPPED_SUBRESOURCE::default();
+            ctx.Map(&staging, 0, D3D11_MAP_READ, 0, Some(&mut mapped))
+                .unwrap();
+            for y in 0..height {
+                let row = (mapped.pData as *const u8)
+                    .add(y * mapped.RowPitch as usize)
+                    as *const Color32;
+                pixels.extend_from_slice(slice::from_raw_parts(row, width));
+            }
+            ctx.Unmap(&staging, 0);
+        }
+        pixels
+    }
+
+    fn managed(pool: &TexturePool) -> &Texture {
+        &pool.pool[&0]
+    }
+
+    #[test]
+    fn failed_map_falls_back_to_update_subresource() {
+        let (ctx, mut pool) = pool();
+        pool.set_keep_cpu_mirror(true).unwrap();
+        set(&mut pool, &ctx, None, image(4, 4, Color32::RED));
+        assert!(managed(&pool).pixels.is_some());
+
+        let device = unsafe { ctx.GetDevice() }.unwrap();
+        let mut deferred = None;
+        unsafe { device.CreateDeferredContext(0, Some(&mut deferred)) }
+            .unwrap();
+        let deferred = deferred.unwrap();
+        // fail mapping the texture, as some drivers do on deferred contexts.
+        let fail_map = |code| {
+            move |_: &ID3D11DeviceContext, _: &Texture| {
+                Err(Error::new(code, "mapping textures fails for this test"))
+            }
+        };
+        let tex = pool.pool.get_mut(&0).unwrap();
+        // other errors are passed on without recreating the texture.
+        let error = TexturePool::update_partial_with(
+            &pool.device,
+            &deferred,
+            tex,
+            image(2, 2, Color32::BLUE),
+            [0, 0],
+            fail_map(E_FAIL),
+        )
+        .unwrap_err();
+        assert_eq!(error.code(), E_FAIL);
+        assert!(tex.pixels.is_some());
+        TexturePool::update_partial_with(
+            &pool.device,
+            &deferred,
+            tex,
+            image(2, 2, Color32::BLUE),
+            [0, 0],
+            fail_map(DXGI_ERROR_INVALID_CALL),
+        )
+        .unwrap();
+        // the texture is recreated once, and the next update goes through
+        // `UpdateSubresource` instead of mapping it again.
+        let tex = managed(&pool).tex.clone();
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe { tex.GetDesc(&mut desc) };
+        assert_eq!(desc.Usage, D3D11_USAGE_DEFAULT);
+        assert!(managed(&pool).pixels.is_none());
+        set(
+            &mut pool,
+            &deferred,
+            Some([2, 2]),
+            image(2, 2, Color32::GREEN),
+        );
+        assert_eq!(managed(&pool).tex, tex);
+
+        let mut command_list = None;
+        unsafe { deferred.FinishCommandList(false, Some(&mut command_list)) }
+            .unwrap();
+        unsafe { ctx.ExecuteCommandList(&command_list.unwrap(), false) };
+        let mut expected = vec![Color32::RED; 16];
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            expected[y * 4 + x] = Color32::BLUE;
+            expected[(y + 2) * 4 + x + 2] = Color32::GREEN;
+        }
+        assert_eq!(read_back(&ctx, &tex), expected);
+    }
+
+    #[test]
+    fn exported_textures_import_unchanged() {
+        let (ctx, mut pool) = pool();
+        pool.set_keep_cpu_mirror(true).unwrap();
+        let pixels = (0..6 * 3)
+            .map(|i| Color32::from_rgba_premultiplied(i, 2 * i, 3 * i, 200))
+            .collect::<Vec<_>>();
+        let color = ColorImage::new([6, 3], pixels.clone());
+        let delta = TexturesDelta {
+            set: vec![(
+                TextureId::Managed(0),
+                ImageDelta::full(
+                    ImageData::Color(color.into()),
+                    TextureOptions::NEAREST_REPEAT,
+                ),
+            )],
+            free: Vec::new(),
+        };
+        pool.update(&ctx, delta).unwrap();
+        let blob = pool.export_managed_textures();
+
+        let (ctx, mut imported) = self::pool();
+        imported.set_keep_cpu_mirror(true).unwrap();
+        assert_eq!(imported.import_managed_textures(&blob).unwrap(), 1);
+        let tex = managed(&imported);
+        assert_eq!((tex.width, tex.height), (6, 3));
+        assert_eq!(
+            tex.sampler,
+            SamplerOptions::from(TextureOptions::NEAREST_REPEAT)
+        );
+        assert_eq!(read_back(&ctx, &tex.tex), pixels);
+        assert_eq!(imported.export_managed_textures(), blob);
+
+        // replaced textures are kept until the next update.
+        let replaced = managed(&imported).tex.clone();
+        assert_eq!(imported.import_managed_textures(&blob).unwrap(), 1);
+        assert_eq!(imported.freed[&0].tex, replaced);
+
+        // truncated blobs are rejected as a whole.
+        assert!(
+            imported
+                .import_managed_textures(&blob[..blob.len() - 1])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn odd_width_uploads_rows_in_place() {
+        // 17 pixels are 68 bytes, which is not a multiple of the row
+        // alignment of any driver, so padded rows would be shifted.
+        let (width, height) = (17, 5);
+        let pixels = (0..width * height)
+            .map(|i| Color32::from_rgba_premultiplied(i as _, 0, 0, 255))
+            .collect::<Vec<_>>();
+        let mut expected = pixels.clone();
+        for y in 1..3 {
+            for x in 3..10 {
+                expected[y * width + x] = Color32::GREEN;
+            }
+        }
+        // with and without a copy, i.e. mapped and `UpdateSubresource`.
+        for keep_cpu_mirror in [false, true] {
+            let (ctx, mut pool) = pool();
+            pool.set_keep_cpu_mirror(keep_cpu_mirror).unwrap();
+            let color = ColorImage::new([width, height], pixels.clone());
+            set(&mut pool, &ctx, None, ImageData::Color(color.into()));
+            assert_eq!(read_back(&ctx, &managed(&pool).tex), pixels);
+            set(&mut pool, &ctx, Some([3, 1]), image(7, 2, Color32::GREEN));
+            assert_eq!(read_back(&ctx, &managed(&pool).tex), expected);
+        }
+    }
+
+    #[test]
+    fn resized_textures_are_replaced_and_freed() {
+        let (ctx, mut pool) = pool();
+        set(&mut pool, &ctx, None, image(4, 4, Color32::RED));
+        let old = managed(&pool).tex.clone();
+
+        // a full update of a different size, e.g. a grown font atlas.
+        set(&mut pool, &ctx, None, image(8, 2, Color32::BLUE));
+        let tex = managed(&pool);
+        assert_ne!(tex.tex, old);
+        assert_eq!((tex.width, tex.height), (8, 2));
+        assert_eq!(read_back(&ctx, &tex.tex), vec![Color32::BLUE; 16]);
+        assert_eq!(pool.freed[&0].tex, old);
+
+        pool.update(&ctx, TexturesDelta::default()).unwrap();
+        assert!(pool.freed.is_empty());
+    }
+
+    #[test]
+    fn freed_textures_are_kept_for_one_update() {
+        let (ctx, mut pool) = pool();
+        set(&mut pool, &ctx, None, image(4, 4, Color32::RED));
+        let tex = managed(&pool).tex.clone();
+        assert!(pool.freed.is_empty());
+
+        set(&mut pool, &ctx, Some([1, 1]), image(2, 2, Color32::GREEN));
+        assert_eq!(managed(&pool).tex, tex);
+        assert!(pool.freed.is_empty());
+        let mut expected = vec![Color32::RED; 16];
+        for (x, y) in [(1, 1), (2, 1), (1, 2), (2, 2)] {
+            expected[y * 4 + x] = Color32::GREEN;
+        }
+        assert_eq!(read_back(&ctx, &tex), expected);
+
+        let free = TexturesDelta {
+            set: Vec::new(),
+            free: vec![TextureId::Managed(0)],
+        };
+        pool.update(&ctx, free).unwrap();
+        assert!(!pool.pool.contains_key(&0));
+        assert_eq!(pool.freed[&0].tex, tex);
+
+        // released by the update after the frame still drawing it.
+        pool.update(&ctx, TexturesDelta::default()).unwrap();
+        assert!(pool.freed.is_empty());
+    }
+
+    #[test]
+    fn oversized_rgba8_images_are_rejected() {
+        let (_, mut pool) = pool();
+        let error = pool.register_rgba8(usize::MAX, 2, &[]).unwrap_err();
+        assert_eq!(error.code(), E_INVALIDARG);
+        let error = pool.register_rgba8(2, 2, &[0; 15]).unwrap_err();
+        assert_eq!(error.code(), E_INVALIDARG);
+    }
+
+    #[test]
+    fn reuploaded_textures_keep_the_replaced_ones() {
+        let (ctx, mut pool) = pool();
+        pool.set_keep_cpu_mirror(true).unwrap();
+        set(&mut pool, &ctx, None, image(4, 4, Color32::RED));
+        let old = managed(&pool).tex.clone();
+        pool.reupload_managed_textures().unwrap();
+        assert_ne!(managed(&pool).tex, old);
+        assert_eq!(pool.freed[&0].tex, old);
+        assert_eq!(
+            read_back(&ctx, &managed(&pool).tex),
+            vec![Color32::RED; 16]
+        );
+    }
+
+    #[test]
+    fn out_of_range_partial_update_is_ignored() {
+        let (ctx, mut pool) = pool();
+        set(&mut pool, &ctx, None, image(4, 4, Color32::RED));
+        set(&mut pool, &ctx, Some([3, 3]), image(2, 2, Color32::BLUE));
+        set(
+            &mut pool,
+            &ctx,
+            Some([usize::MAX, 0]),
+            image(2, 2, Color32::BLUE),
+        );
+        set(
+            &mut pool,
+            &ctx,
+            Some([0, usize::MAX]),
+            image(2, 2, Color32::BLUE),
+        );
+        let tex = managed(&pool);
+        assert_eq!(read_back(&ctx, &tex.tex), vec![Color32::RED; 16]);
+    }
+}
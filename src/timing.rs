@@ -0,0 +1,153 @@
+use std::mem;
+
+use windows::Win32::{Foundation::S_FALSE, Graphics::Direct3D11::*};
+use windows::core::{Interface, Result};
+
+/// Number of frames whose queries may be in flight. Results are usually
+/// available two or three frames after being issued.
+const FRAMES_IN_FLIGHT: usize = 4;
+
+struct Queries {
+    disjoint: ID3D11Query,
+    start: ID3D11Query,
+    end: ID3D11Query,
+    // frame the queries were issued in, if their results are pending.
+    pending: Option<u64>,
+}
+
+/// Measures the GPU time of rendering with timestamp queries. Results are
+/// read back without stalling, so they lag a few frames behind.
+pub struct GpuTimer {
+    queries: Vec<Queries>,
+    next: usize,
+    active: bool,
+    frame: u64,
+    last: Option<(u64, f32)>,
+}
+
+impl GpuTimer {
+    pub fn new(device: &ID3D11Device) -> Result<Self> {
+        let queries = (0..FRAMES_IN_FLIGHT)
+            .map(|_| {
+                Ok(Queries {
+                    disjoint: Self::create_query(
+                        device,
+                        D3D11_QUERY_TIMESTAMP_DISJOINT,
+                    )?,
+                    start: Self::create_query(device, D3D11_QUERY_TIMESTAMP)?,
+                    end: Self::create_query(device, D3D11_QUERY_TIMESTAMP)?,
+                    pending: None,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            queries,
+            next: 0,
+            active: false,
+            frame: 0,
+            last: None,
+        })
+    }
+
+    fn create_query(
+        device: &ID3D11Device,
+        query: D3D11_QUERY,
+    ) -> Result<ID3D11Query> {
+        let desc = D3D11_QUERY_DESC {
+            Query: query,
+            MiscFlags: 0,
+        };
+        let mut query = None;
+        unsafe { device.CreateQuery(&desc, Some(&mut query)) }?;
+        Ok(query.unwrap())
+    }
+
+    /// GPU time of the most recent frame whose results are available, in
+    /// milliseconds.
+    pub fn last_time_ms(&self) -> Option<f32> {
+        self.last.map(|(_, time)| time)
+    }
+
+    pub fn begin(&mut self, ctx: &ID3D11DeviceContext) -> Result<()> {
+        self.poll(ctx)?;
+        let queries = &self.queries[self.next];
+        if queries.pending.is_some() {
+            // all queries are in flight, skip measuring this frame.
+            return Ok(());
+        }
+        unsafe {
+            ctx.Begin(&queries.disjoint);
+            ctx.End(&queries.start);
+        }
+        self.active = true;
+        Ok(())
+    }
+
+    pub fn end(&mut self, ctx: &ID3D11DeviceContext) {
+        if !self.active {
+            return;
+        }
+        let queries = &mut self.queries[self.next];
+        unsafe {
+            ctx.End(&queries.end);
+            ctx.End(&queries.disjoint);
+        }
+        queries.pending = Some(self.frame);
+        self.active = false;
+        self.frame += 1;
+        self.next = (self.next + 1) % FRAMES_IN_FLIGHT;
+    }
+
+    fn poll(&mut self, ctx: &ID3D11DeviceContext) -> Result<()> {
+        for queries in &mut self.queries {
+            let Some(frame) = queries.pending else {
+                continue;
+            };
+            let Some(disjoint) = get_query_data::<
+                D3D11_QUERY_DATA_TIMESTAMP_DISJOINT,
+            >(ctx, &queries.disjoint)?
+            else {
+                continue;
+            };
+            let start = get_query_data::<u64>(ctx, &queries.start)?;
+            let end = get_query_data::<u64>(ctx, &queries.end)?;
+            let (Some(start), Some(end)) = (start, end) else {
+                continue;
+            };
+            queries.pending = None;
+            // the measurement is unreliable if the GPU clock has changed,
+            // e.g. due to power management.
+            if disjoint.Disjoint.as_bool() || disjoint.Frequency == 0 {
+                continue;
+            }
+            let time = end.saturating_sub(start) as f64 * 1000.
+                / disjoint.Frequency as f64;
+            if self.last.is_none_or(|(last, _)| last < frame) {
+                self.last = Some((frame, time as f32));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the result of `query`, or `None` if it is not yet available, without
+/// flushing the device context.
+pub fn get_query_data<T>(
+    ctx: &ID3D11DeviceContext,
+    query: &ID3D11Query,
+) -> Result<Option<T>> {
+    let mut data = mem::MaybeUninit::<T>::uninit();
+    // `ID3D11DeviceContext::GetData` maps `S_FALSE`, which means that the
+    // data is not yet available, to `Ok` as well.
+    let hr = unsafe {
+        (Interface::vtable(ctx).GetData)(
+            ctx.as_raw(),
+            query.as_raw(),
+            data.as_mut_ptr() as _,
+            mem::size_of::<T>() as _,
+            D3D11_ASYNC_GETDATA_DONOTFLUSH.0 as _,
+        )
+    };
+    hr.ok()?;
+    Ok((hr != S_FALSE).then(|| unsafe { data.assume_init() }))
+}